@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::grid::{DIR, Grid, Point};
+use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+use crate::grid_mask::BitRow;
+use crate::scc::strongly_connected_components;
+
+/// What each tile does to a beam entering it, per incoming direction. A tile with no
+/// outputs absorbs the beam.
+#[derive(Debug, Clone)]
+pub struct TileTable {
+    rules: HashMap<char, [Vec<DIR>; 4]>,
+}
+
+impl TileTable {
+    pub fn new() -> TileTable {
+        return TileTable { rules: HashMap::new() };
+    }
+
+    /// Empty space, the two mirrors and the two splitters.
+    pub fn standard() -> TileTable {
+        return TileTable::new()
+            .with_tile('.', |d| vec![d])
+            .with_tile('/', |d| match d {
+                NORTH => vec![EAST],
+                EAST => vec![NORTH],
+                SOUTH => vec![WEST],
+                WEST => vec![SOUTH],
+            })
+            .with_tile('\\', |d| match d {
+                NORTH => vec![WEST],
+                EAST => vec![SOUTH],
+                SOUTH => vec![EAST],
+                WEST => vec![NORTH],
+            })
+            .with_tile('|', |d| match d {
+                NORTH | SOUTH => vec![d],
+                EAST | WEST => vec![NORTH, SOUTH],
+            })
+            .with_tile('-', |d| match d {
+                EAST | WEST => vec![d],
+                NORTH | SOUTH => vec![EAST, WEST],
+            });
+    }
+
+    pub fn with_tile<F: Fn(DIR) -> Vec<DIR>>(mut self, tile: char, behaviour: F) -> TileTable {
+        self.rules.insert(tile, DIR::ALL.map(&behaviour));
+        return self;
+    }
+
+    pub fn outputs(&self, tile: char, dir: &DIR) -> Option<&Vec<DIR>> {
+        return self.rules.get(&tile).map(|r| &r[dir.index()]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownTile {
+    pub tile: char,
+    pub at: Point,
+}
+
+/// Cells lit by following a beam until it splits, is absorbed, leaves the grid or loops,
+/// and the beam states it splits into.
+struct Segment {
+    cells: BitRow,
+    next: Vec<usize>,
+}
+
+/// Answers "how many cells does a beam entering at `(p, dir)` energize" without
+/// re-simulating. Every beam leaving a splitter starts a segment; segments form a graph
+/// whose strongly connected components share one energized set, built bottom-up once.
+pub struct BeamEngine {
+    grid: Grid,
+    outputs: Vec<Vec<DIR>>,
+    // beam state id -> index into `reach`
+    node_reach: HashMap<usize, usize>,
+    reach: Vec<BitRow>,
+}
+
+impl BeamEngine {
+    pub fn new(grid: &Grid, table: &TileTable) -> Result<BeamEngine, UnknownTile> {
+        let mut outputs = vec![];
+        for y in 0..grid.y_size {
+            for x in 0..grid.x_size {
+                let tile = *grid.get(x, y).unwrap();
+                for d in &DIR::ALL {
+                    let out = table.outputs(tile, d).ok_or(UnknownTile { tile, at: Point { x, y } })?;
+                    outputs.push(out.clone());
+                }
+            }
+        }
+
+        let mut engine = BeamEngine {
+            grid: grid.clone(),
+            outputs,
+            node_reach: HashMap::new(),
+            reach: vec![],
+        };
+        engine.build();
+
+        return Ok(engine);
+    }
+
+    pub fn edge_entries(&self) -> Vec<(Point, DIR)> {
+        let (w, h) = (self.grid.x_size, self.grid.y_size);
+
+        return (0..w)
+            .map(|x| (Point { x, y: 0 }, SOUTH))
+            .chain((0..w).map(|x| (Point { x, y: h - 1 }, NORTH)))
+            .chain((0..h).map(|y| (Point { x: 0, y }, EAST)))
+            .chain((0..h).map(|y| (Point { x: w - 1, y }, WEST)))
+            .collect_vec();
+    }
+
+    pub fn energized(&self, p: Point, dir: DIR) -> usize {
+        return self.energized_cells(p, dir).count_ones() as usize;
+    }
+
+    /// Bit `y * width + x` is set for every energized cell.
+    pub fn energized_cells(&self, p: Point, dir: DIR) -> BitRow {
+        let start = self.state(&p, &dir);
+        if let Some(r) = self.node_reach.get(&start) {
+            return self.reach[*r].clone();
+        }
+
+        let segment = self.segment(start);
+        let mut cells = segment.cells;
+        for n in segment.next {
+            cells.union_with(&self.reach[self.node_reach[&n]]);
+        }
+
+        return cells;
+    }
+
+    pub fn max_energized(&self) -> usize {
+        return self.edge_entries()
+            .into_iter()
+            .map(|(p, d)| self.energized(p, d))
+            .max()
+            .unwrap_or(0);
+    }
+
+    fn state(&self, p: &Point, dir: &DIR) -> usize {
+        return (p.y * self.grid.x_size + p.x) * 4 + dir.index();
+    }
+
+    fn segment(&self, start: usize) -> Segment {
+        let mut cells = BitRow::new(self.grid.x_size * self.grid.y_size);
+        let mut walked = BitRow::new(self.outputs.len());
+        let mut current = start;
+
+        loop {
+            walked.set(current, true);
+            let cell = current / 4;
+            cells.set(cell, true);
+
+            let p = Point { x: cell % self.grid.x_size, y: cell / self.grid.x_size };
+            let next = self.outputs[current]
+                .iter()
+                .filter_map(|d| self.grid.try_move(&p, d).map(|np| self.state(&np, d)))
+                .collect_vec();
+
+            if self.outputs[current].len() != 1 {
+                return Segment { cells, next };
+            }
+
+            match next.first() {
+                Some(n) if !walked.get(*n) => current = *n,
+                _ => return Segment { cells, next: vec![] },
+            }
+        }
+    }
+
+    fn build(&mut self) {
+        // every beam leaving a splitter, plus every edge entry, starts a segment
+        let mut starts = self.edge_entries()
+            .iter()
+            .map(|(p, d)| self.state(p, d))
+            .collect_vec();
+        for s in 0..self.outputs.len() {
+            if self.outputs[s].len() > 1 {
+                let cell = s / 4;
+                let p = Point { x: cell % self.grid.x_size, y: cell / self.grid.x_size };
+                starts.extend(self.outputs[s].iter().filter_map(|d| self.grid.try_move(&p, d).map(|np| self.state(&np, d))));
+            }
+        }
+
+        let nodes = starts.into_iter().unique().collect_vec();
+        let index: HashMap<usize, usize> = nodes.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        let segments = nodes.iter().map(|s| self.segment(*s)).collect_vec();
+        let edges = segments
+            .iter()
+            .map(|seg| seg.next.iter().map(|n| index[n]).collect_vec())
+            .collect_vec();
+
+        // components come sinks first, so successors are always ready
+        let mut reach = vec![];
+        let mut component_of = vec![0; nodes.len()];
+        for (c, members) in strongly_connected_components(&edges).iter().enumerate() {
+            for m in members {
+                component_of[*m] = c;
+            }
+
+            let mut cells = BitRow::new(self.grid.x_size * self.grid.y_size);
+            for m in members {
+                cells.union_with(&segments[*m].cells);
+                for n in &edges[*m] {
+                    if component_of[*n] != c {
+                        cells.union_with(&reach[component_of[*n]]);
+                    }
+                }
+            }
+            reach.push(cells);
+        }
+
+        self.node_reach = nodes.iter().enumerate().map(|(i, s)| (*s, component_of[i])).collect();
+        self.reach = reach;
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::{HashMap, VecDeque};
+
+use itertools::Itertools;
+
+use crate::circuit::PV::{HIGH, LOW};
+use crate::vcd::{TraceFilter, TraceRecorder};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PV {
+    HIGH,
+    LOW,
+}
+
+/// Behaviour of one kind of module. Modules are built before any wiring exists and are
+/// told about each of their inputs once, before the first pulse.
+pub trait Module {
+    fn connect_input(&mut self, _from: usize) {}
+    fn receive(&mut self, from: usize, value: PV) -> Option<PV>;
+    fn reset(&mut self);
+}
+
+struct FlipFlop {
+    on: bool,
+}
+
+impl Module for FlipFlop {
+    fn receive(&mut self, _from: usize, value: PV) -> Option<PV> {
+        if value == HIGH {
+            return None;
+        }
+
+        self.on = !self.on;
+        return Some(if self.on { HIGH } else { LOW });
+    }
+
+    fn reset(&mut self) {
+        self.on = false;
+    }
+}
+
+struct Conjunction {
+    // most recent pulse from each input, in wiring order
+    memory: Vec<(usize, PV)>,
+}
+
+impl Module for Conjunction {
+    fn connect_input(&mut self, from: usize) {
+        self.memory.push((from, LOW));
+    }
+
+    fn receive(&mut self, from: usize, value: PV) -> Option<PV> {
+        if let Some(m) = self.memory.iter_mut().find(|(f, _)| *f == from) {
+            m.1 = value;
+        }
+
+        return Some(if self.memory.iter().all(|(_, v)| *v == HIGH) { LOW } else { HIGH });
+    }
+
+    fn reset(&mut self) {
+        for m in &mut self.memory {
+            m.1 = LOW;
+        }
+    }
+}
+
+struct Broadcast;
+
+impl Module for Broadcast {
+    fn receive(&mut self, _from: usize, value: PV) -> Option<PV> {
+        return Some(value);
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// A module named as a destination but never declared, like `output` or `rx`.
+struct Sink;
+
+impl Module for Sink {
+    fn receive(&mut self, _from: usize, _value: PV) -> Option<PV> {
+        return None;
+    }
+
+    fn reset(&mut self) {}
+}
+
+pub const FLIP_FLOP: &str = "flip-flop";
+pub const CONJUNCTION: &str = "conjunction";
+pub const BROADCASTER: &str = "broadcaster";
+pub const SINK: &str = "sink";
+
+#[derive(Clone)]
+struct ModuleKind {
+    kind: &'static str,
+    factory: fn() -> Box<dyn Module>,
+}
+
+/// Maps declarations to module kinds, either by a one-character prefix on the name
+/// (`%a`) or by an exact name (`broadcaster`).
+#[derive(Clone)]
+pub struct ModuleRegistry {
+    prefixes: HashMap<char, ModuleKind>,
+    names: HashMap<String, ModuleKind>,
+}
+
+impl ModuleRegistry {
+    pub fn new() -> ModuleRegistry {
+        return ModuleRegistry { prefixes: HashMap::new(), names: HashMap::new() };
+    }
+
+    pub fn standard() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry.register_prefix('%', FLIP_FLOP, || Box::new(FlipFlop { on: false }));
+        registry.register_prefix('&', CONJUNCTION, || Box::new(Conjunction { memory: vec![] }));
+        registry.register_name("broadcaster", BROADCASTER, || Box::new(Broadcast));
+
+        return registry;
+    }
+
+    pub fn register_prefix(&mut self, prefix: char, kind: &'static str, factory: fn() -> Box<dyn Module>) {
+        self.prefixes.insert(prefix, ModuleKind { kind, factory });
+    }
+
+    pub fn register_name(&mut self, name: &str, kind: &'static str, factory: fn() -> Box<dyn Module>) {
+        self.names.insert(name.to_owned(), ModuleKind { kind, factory });
+    }
+
+    /// The module's name and kind for a declaration like `%a` or `broadcaster`.
+    fn resolve(&self, declared: &str) -> Option<(String, &ModuleKind)> {
+        if let Some(kind) = self.names.get(declared) {
+            return Some((declared.to_owned(), kind));
+        }
+
+        let prefix = declared.chars().next()?;
+        let name = &declared[prefix.len_utf8()..];
+        return self.prefixes
+            .get(&prefix)
+            .filter(|_| !name.is_empty())
+            .map(|kind| (name.to_owned(), kind));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitError {
+    Malformed { line: usize, text: String },
+    UnknownKind { line: usize, declared: String },
+    Duplicate { line: usize, name: String },
+    UnknownModule { name: String },
+}
+
+/// One pulse delivered during a button press; `seq` counts pulses within the press.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub press: usize,
+    pub seq: usize,
+    pub from: usize,
+    pub to: usize,
+    pub value: PV,
+}
+
+/// Matches pulses by sender, receiver and value; `None` matches anything.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Probe {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub value: Option<PV>,
+}
+
+impl Probe {
+    pub fn sent(from: &str, value: PV) -> Probe {
+        return Probe { from: Some(from.to_owned()), to: None, value: Some(value) };
+    }
+
+    pub fn received(to: &str, value: PV) -> Probe {
+        return Probe { from: None, to: Some(to.to_owned()), value: Some(value) };
+    }
+}
+
+struct ResolvedProbe {
+    from: Option<usize>,
+    to: Option<usize>,
+    value: Option<PV>,
+}
+
+impl ResolvedProbe {
+    fn matches(&self, e: &Event) -> bool {
+        return self.from.is_none_or(|f| f == e.from)
+            && self.to.is_none_or(|t| t == e.to)
+            && self.value.is_none_or(|v| v == e.value);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PressReport {
+    pub low: usize,
+    pub high: usize,
+    // hits per probe, in the order the probes were added
+    pub probes: Vec<usize>,
+}
+
+/// Modules are numbered in declaration order, with undeclared destinations appended as
+/// sinks, and pulses are processed first in first out, so a run is fully deterministic.
+pub struct Machine {
+    names: Vec<String>,
+    kinds: Vec<&'static str>,
+    modules: Vec<Box<dyn Module>>,
+    outputs: Vec<Vec<usize>>,
+    inputs: Vec<Vec<usize>>,
+    index: HashMap<String, usize>,
+    probes: Vec<ResolvedProbe>,
+    log: Option<Vec<Event>>,
+    trace: Option<TraceRecorder>,
+    presses: usize,
+    pub low: usize,
+    pub high: usize,
+}
+
+pub const BUTTON: usize = usize::MAX;
+
+pub fn parse_machine(input: &str) -> Result<Machine, CircuitError> {
+    return parse_machine_with(input, &ModuleRegistry::standard());
+}
+
+pub fn parse_machine_with(input: &str, registry: &ModuleRegistry) -> Result<Machine, CircuitError> {
+    let mut declared = vec![];
+    for (i, l) in input.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let (prefix, suffix) = l.split_once(" -> ")
+            .ok_or(CircuitError::Malformed { line: i + 1, text: l.to_owned() })?;
+        let (name, kind) = registry.resolve(prefix.trim())
+            .ok_or(CircuitError::UnknownKind { line: i + 1, declared: prefix.trim().to_owned() })?;
+        let outputs = suffix.split(',').map(|s| s.trim().to_owned()).collect_vec();
+        if outputs.iter().any(|o| o.is_empty()) {
+            return Err(CircuitError::Malformed { line: i + 1, text: l.to_owned() });
+        }
+
+        if declared.iter().any(|(n, _, _, _)| *n == name) {
+            return Err(CircuitError::Duplicate { line: i + 1, name });
+        }
+        declared.push((name, kind.kind, (kind.factory)(), outputs));
+    }
+
+    // first phase: every module exists, including the undeclared sinks
+    let mut names = declared.iter().map(|(n, _, _, _)| n.clone()).collect_vec();
+    for o in declared.iter().flat_map(|(_, _, _, o)| o) {
+        if !names.contains(o) {
+            names.push(o.clone());
+        }
+    }
+    let index: HashMap<String, usize> = names.iter().enumerate().map(|(i, n)| (n.clone(), i)).collect();
+
+    let mut kinds = vec![];
+    let mut modules = vec![];
+    let mut outputs = vec![];
+    for (_, kind, module, outs) in declared {
+        kinds.push(kind);
+        modules.push(module);
+        outputs.push(outs.iter().map(|o| index[o]).collect_vec());
+    }
+    while modules.len() < names.len() {
+        kinds.push(SINK);
+        modules.push(Box::new(Sink) as Box<dyn Module>);
+        outputs.push(vec![]);
+    }
+
+    // second phase: tell every module about its inputs
+    let mut inputs = vec![vec![]; names.len()];
+    for (from, outs) in outputs.iter().enumerate() {
+        for to in outs {
+            modules[*to].connect_input(from);
+            inputs[*to].push(from);
+        }
+    }
+
+    return Ok(Machine {
+        names,
+        kinds,
+        modules,
+        outputs,
+        inputs,
+        index,
+        probes: vec![],
+        log: None,
+        trace: None,
+        presses: 0,
+        low: 0,
+        high: 0,
+    });
+}
+
+impl Machine {
+    pub fn len(&self) -> usize {
+        return self.names.len();
+    }
+
+    pub fn index(&self, name: &str) -> Option<usize> {
+        return self.index.get(name).copied();
+    }
+
+    pub fn name(&self, module: usize) -> &str {
+        if module == BUTTON {
+            return "button";
+        }
+
+        return &self.names[module];
+    }
+
+    pub fn kind(&self, module: usize) -> &'static str {
+        return self.kinds[module];
+    }
+
+    pub fn outputs(&self, module: usize) -> &Vec<usize> {
+        return &self.outputs[module];
+    }
+
+    pub fn inputs(&self, module: usize) -> &Vec<usize> {
+        return &self.inputs[module];
+    }
+
+    pub fn presses(&self) -> usize {
+        return self.presses;
+    }
+
+    /// Returns the probe's position in every `PressReport::probes`.
+    pub fn add_probe(&mut self, probe: &Probe) -> Result<usize, CircuitError> {
+        let resolve = |name: &Option<String>| -> Result<Option<usize>, CircuitError> {
+            return match name {
+                None => Ok(None),
+                Some(n) => self.index(n).map(Some).ok_or(CircuitError::UnknownModule { name: n.clone() }),
+            };
+        };
+
+        let resolved = ResolvedProbe {
+            from: resolve(&probe.from)?,
+            to: resolve(&probe.to)?,
+            value: probe.value,
+        };
+        self.probes.push(resolved);
+
+        return Ok(self.probes.len() - 1);
+    }
+
+    pub fn probe_count(&self) -> usize {
+        return self.probes.len();
+    }
+
+    /// Drops the probes added after the first `len`, keeping the others' positions.
+    pub fn truncate_probes(&mut self, len: usize) {
+        self.probes.truncate(len);
+    }
+
+    /// Starts recording every pulse from the next press on.
+    pub fn enable_log(&mut self) {
+        self.log = Some(vec![]);
+    }
+
+    pub fn log(&self) -> Option<&Vec<Event>> {
+        return self.log.as_ref();
+    }
+
+    /// Starts tracing the modules `filter` selects from the next press on.
+    pub fn record_trace(&mut self, filter: &TraceFilter) -> Result<(), CircuitError> {
+        self.trace = Some(TraceRecorder::new(self, filter)?);
+        return Ok(());
+    }
+
+    pub fn trace(&self) -> Option<&TraceRecorder> {
+        return self.trace.as_ref();
+    }
+
+    pub fn reset(&mut self) {
+        for m in &mut self.modules {
+            m.reset();
+        }
+        if let Some(log) = &mut self.log {
+            log.clear();
+        }
+        if let Some(trace) = &mut self.trace {
+            trace.clear();
+        }
+        self.presses = 0;
+        self.low = 0;
+        self.high = 0;
+    }
+
+    pub fn push_button(&mut self) -> PressReport {
+        self.presses += 1;
+        let mut report = PressReport { low: 0, high: 0, probes: vec![0; self.probes.len()] };
+        let mut queue = VecDeque::new();
+
+        let Some(broadcaster) = self.index("broadcaster") else {
+            return report;
+        };
+        queue.push_back(Event { press: self.presses, seq: 0, from: BUTTON, to: broadcaster, value: LOW });
+        let mut seq = 0;
+
+        while let Some(event) = queue.pop_front() {
+            match event.value {
+                LOW => report.low += 1,
+                HIGH => report.high += 1,
+            }
+            for (i, p) in self.probes.iter().enumerate() {
+                if p.matches(&event) {
+                    report.probes[i] += 1;
+                }
+            }
+            if let Some(log) = &mut self.log {
+                log.push(event);
+            }
+            if let Some(trace) = &mut self.trace {
+                trace.record(&event);
+            }
+
+            if let Some(value) = self.modules[event.to].receive(event.from, event.value) {
+                for to in &self.outputs[event.to] {
+                    seq += 1;
+                    queue.push_back(Event { press: self.presses, seq, from: event.to, to: *to, value });
+                }
+            }
+        }
+
+        self.low += report.low;
+        self.high += report.high;
+        return report;
+    }
+}
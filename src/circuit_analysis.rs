@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use num::integer::{ExtendedGcd, Integer};
+
+use crate::circuit::{BROADCASTER, CONJUNCTION, FLIP_FLOP, Machine, Probe, SINK};
+use crate::circuit::PV::HIGH;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    UnknownModule { name: String },
+    NotDecomposable { reason: String },
+    NoPeriod { module: String, presses: usize },
+    NeverCoincide,
+}
+
+/// One input of the conjunction feeding the target, with every module upstream of it
+/// apart from the broadcaster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubCircuit {
+    pub output: usize,
+    pub modules: Vec<usize>,
+}
+
+/// Presses `offset`, `offset + period`, `offset + 2 * period`, ...
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Periodic {
+    pub offset: u64,
+    pub period: u64,
+}
+
+fn upstream(machine: &Machine, module: usize) -> HashSet<usize> {
+    let mut seen = HashSet::from([module]);
+    let mut stack = vec![module];
+    while let Some(m) = stack.pop() {
+        for i in machine.inputs(m) {
+            if machine.kind(*i) != BROADCASTER && seen.insert(*i) {
+                stack.push(*i);
+            }
+        }
+    }
+
+    return seen;
+}
+
+/// The conjunction that alone feeds `target`, and its inputs split into sub-circuits
+/// sharing nothing but the broadcaster.
+pub fn independent_subcircuits(machine: &Machine, target: &str) -> Result<(usize, Vec<SubCircuit>), AnalysisError> {
+    let t = machine.index(target).ok_or(AnalysisError::UnknownModule { name: target.to_owned() })?;
+
+    let hub = match machine.inputs(t).as_slice() {
+        [hub] if machine.kind(*hub) == CONJUNCTION => *hub,
+        _ => return Err(AnalysisError::NotDecomposable { reason: format!("{target} isn't fed by a single conjunction") }),
+    };
+
+    let mut claimed = HashSet::from([hub]);
+    let mut subcircuits = vec![];
+    for i in machine.inputs(hub) {
+        let modules = upstream(machine, *i).into_iter().sorted().collect_vec();
+        if let Some(shared) = modules.iter().find(|m| claimed.contains(m)) {
+            return Err(AnalysisError::NotDecomposable {
+                reason: format!("{} feeds more than one input of {}", machine.name(*shared), machine.name(hub)),
+            });
+        }
+
+        claimed.extend(&modules);
+        subcircuits.push(SubCircuit { output: *i, modules });
+    }
+
+    return Ok((hub, subcircuits));
+}
+
+/// Presses `machine` from a reset until each sub-circuit's output has sent a high pulse
+/// to `hub` three times, and checks the gaps agree. The machine is left reset.
+pub fn measure_periods(machine: &mut Machine, hub: usize, subcircuits: &[SubCircuit], limit: usize) -> Result<Vec<Periodic>, AnalysisError> {
+    machine.reset();
+    let first = machine.probe_count();
+    for s in subcircuits {
+        let probe = Probe {
+            from: Some(machine.name(s.output).to_owned()),
+            to: Some(machine.name(hub).to_owned()),
+            value: Some(HIGH),
+        };
+        machine.add_probe(&probe).unwrap();
+    }
+
+    let mut hits = vec![vec![]; subcircuits.len()];
+    while machine.presses() < limit && hits.iter().any(|h| h.len() < 3) {
+        let report = machine.push_button();
+        for (h, n) in hits.iter_mut().zip(&report.probes[first..]) {
+            if *n > 0 && h.len() < 3 {
+                h.push(machine.presses() as u64);
+            }
+        }
+    }
+    machine.truncate_probes(first);
+    machine.reset();
+
+    return subcircuits
+        .iter()
+        .zip(hits)
+        .map(|(s, h)| match h.as_slice() {
+            [a, b, c] if b - a == c - b => Ok(Periodic { offset: *a, period: b - a }),
+            _ => Err(AnalysisError::NoPeriod { module: machine.name(s.output).to_owned(), presses: limit }),
+        })
+        .collect();
+}
+
+/// Merges two periodic press sequences into the one of presses in both, if any.
+fn combine(a: Periodic, b: Periodic) -> Option<Periodic> {
+    let (p, q) = (a.period as i128, b.period as i128);
+    let ExtendedGcd { gcd, x, .. } = p.extended_gcd(&q);
+    let diff = b.offset as i128 - a.offset as i128;
+    if diff % gcd != 0 {
+        return None;
+    }
+
+    let period = p / gcd * q;
+    let base = (a.offset as i128 + p * (diff / gcd * x).rem_euclid(q / gcd)).rem_euclid(period);
+
+    // the first common press at or after both offsets
+    let floor = a.offset.max(b.offset) as i128;
+    let offset = base + ((floor - base).max(0) + period - 1) / period * period;
+
+    return Some(Periodic { offset: offset as u64, period: period as u64 });
+}
+
+/// The first press on which `target` receives a low pulse, assuming the hub's inputs each
+/// pulse high periodically and the hub fires when they all do so on the same press.
+pub fn presses_until_low(machine: &mut Machine, target: &str, limit: usize) -> Result<u64, AnalysisError> {
+    let (hub, subcircuits) = independent_subcircuits(machine, target)?;
+    let periods = measure_periods(machine, hub, &subcircuits, limit)?;
+
+    return periods
+        .into_iter()
+        .try_fold(Periodic { offset: 1, period: 1 }, combine)
+        .map(|p| p.offset)
+        .ok_or(AnalysisError::NeverCoincide);
+}
+
+/// The module network in Graphviz DOT, one shape per module kind.
+pub fn to_dot(machine: &Machine) -> String {
+    let mut lines = vec!["digraph circuit {".to_owned()];
+    for m in 0..machine.len() {
+        let shape = match machine.kind(m) {
+            FLIP_FLOP => "box",
+            CONJUNCTION => "diamond",
+            BROADCASTER => "doublecircle",
+            SINK => "plaintext",
+            _ => "ellipse",
+        };
+        lines.push(format!("    \"{}\" [shape={shape}];", machine.name(m)));
+    }
+    for m in 0..machine.len() {
+        for o in machine.outputs(m) {
+            lines.push(format!("    \"{}\" -> \"{}\";", machine.name(m), machine.name(*o)));
+        }
+    }
+    lines.push("}".to_owned());
+
+    return lines.join("\n");
+}
+
+/// A chain of flip-flops driven by the broadcaster, lowest bit first, with a conjunction
+/// reading the set bits and feeding back into the first bit and the clear ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter {
+    pub bits: Vec<usize>,
+    pub hub: usize,
+    // bits wired into the hub, highest first
+    pub pattern: String,
+    pub period: u64,
+}
+
+/// Finds the binary counters hanging off the broadcaster from the wiring alone. Each one
+/// sends a low pulse out of its hub every `period` presses, `period` being its pattern
+/// read as a binary number.
+pub fn binary_counters(machine: &Machine) -> Vec<Counter> {
+    let Some(broadcaster) = machine.index("broadcaster") else {
+        return vec![];
+    };
+
+    return machine.outputs(broadcaster)
+        .iter()
+        .filter_map(|first| counter_from(machine, *first))
+        .collect_vec();
+}
+
+fn counter_from(machine: &Machine, first: usize) -> Option<Counter> {
+    if machine.kind(first) != FLIP_FLOP {
+        return None;
+    }
+
+    let mut bits = vec![first];
+    let mut hubs = HashSet::new();
+    loop {
+        let current = *bits.last().unwrap();
+        let (flip_flops, others): (Vec<usize>, Vec<usize>) = machine.outputs(current)
+            .iter()
+            .partition(|o| machine.kind(**o) == FLIP_FLOP);
+        if others.iter().any(|o| machine.kind(*o) != CONJUNCTION) {
+            return None;
+        }
+        hubs.extend(others);
+
+        match flip_flops.as_slice() {
+            [] => break,
+            [next] if !bits.contains(next) => bits.push(*next),
+            _ => return None,
+        }
+    }
+
+    let [hub] = hubs.into_iter().collect_vec()[..] else {
+        return None;
+    };
+    let set = bits.iter().map(|b| machine.outputs(*b).contains(&hub)).collect_vec();
+
+    // the hub reads exactly the set bits and resets the first bit and the clear ones
+    let readers = machine.inputs(hub).iter().copied().sorted().collect_vec();
+    let expected_readers = bits.iter().zip(&set).filter(|(_, s)| **s).map(|(b, _)| *b).sorted().collect_vec();
+    let resets = machine.outputs(hub).iter().copied().filter(|o| bits.contains(o)).sorted().collect_vec();
+    let expected_resets = bits.iter().zip(&set).enumerate().filter(|(i, (_, s))| *i == 0 || !**s).map(|(_, (b, _))| *b).sorted().collect_vec();
+    if readers != expected_readers || resets != expected_resets {
+        return None;
+    }
+
+    let pattern = set.iter().rev().map(|s| if *s { '1' } else { '0' }).collect::<String>();
+    let period = u64::from_str_radix(&pattern, 2).ok()?;
+
+    return Some(Counter { bits, hub, pattern, period });
+}
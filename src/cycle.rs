@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A state sequence `x0, f(x0), f(f(x0)), ...` that becomes periodic after `offset` steps.
+/// `history` holds every state from step 0 up to (but not including) the first repeat.
+#[derive(Debug, Clone)]
+pub struct Cycle<T> {
+    pub offset: usize,
+    pub period: usize,
+    history: Vec<T>,
+}
+
+impl<T> Cycle<T> {
+    pub fn state_at(&self, n: usize) -> &T {
+        return &self.history[reduce_step(self.offset, self.period, n)];
+    }
+}
+
+/// Maps step `n` onto the equivalent step inside the first lap of the cycle.
+pub fn reduce_step(offset: usize, period: usize, n: usize) -> usize {
+    if n < offset + period {
+        return n;
+    }
+
+    return offset + (n - offset) % period;
+}
+
+/// Hash-based detection: remembers every state seen, so it finds the cycle in
+/// `offset + period` steps and can answer `state_at` without re-running `f`.
+pub fn find_cycle<T: Hash + Eq + Clone, F: FnMut(&T) -> T>(start: T, mut f: F) -> Cycle<T> {
+    let mut seen: HashMap<T, usize> = HashMap::new();
+    let mut history = vec![];
+    let mut current = start;
+
+    loop {
+        if let Some(&first) = seen.get(&current) {
+            return Cycle {
+                offset: first,
+                period: history.len() - first,
+                history,
+            };
+        }
+
+        seen.insert(current.clone(), history.len());
+        let next = f(&current);
+        history.push(current);
+        current = next;
+    }
+}
+
+/// Brent's algorithm: constant memory, returns `(offset, period)`. `f` is called
+/// several times per state, so it should be cheap and pure.
+pub fn brent<T: Eq + Clone, F: Fn(&T) -> T>(start: &T, f: F) -> (usize, usize) {
+    let mut power = 1usize;
+    let mut period = 1usize;
+    let mut tortoise = start.clone();
+    let mut hare = f(start);
+
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = f(&hare);
+        period += 1;
+    }
+
+    tortoise = start.clone();
+    hare = start.clone();
+    for _ in 0..period {
+        hare = f(&hare);
+    }
+
+    let mut offset = 0usize;
+    while tortoise != hare {
+        tortoise = f(&tortoise);
+        hare = f(&hare);
+        offset += 1;
+    }
+
+    return (offset, period);
+}
+
+/// State after `n` applications of `f`, using Brent's algorithm to skip whole laps.
+pub fn brent_state_at<T: Eq + Clone, F: Fn(&T) -> T>(start: &T, f: F, n: usize) -> T {
+    let (offset, period) = brent(start, &f);
+
+    let mut current = start.clone();
+    for _ in 0..reduce_step(offset, period, n) {
+        current = f(&current);
+    }
+
+    return current;
+}
@@ -1,11 +1,12 @@
 #[cfg(test)]
 mod day05 {
-    use std::cmp;
+    use std::iter;
     use std::ops::Range;
 
     use itertools::Itertools;
     use regex::Regex;
 
+    use crate::range_set::{RangeMap, RangeSet};
     use crate::read_data_file;
 
     static SAMPLE: &str = r#"seeds: 79 14 55 13
@@ -62,32 +63,6 @@ humidity-to-location map:
 
             return None;
         }
-
-        fn intersect(&self, rng: &Range<u64>) -> (Vec<Range<u64>>, Vec<Range<u64>>) {
-            let mut unmatched: Vec<Range<u64>> = Vec::new();
-            let mut matched_and_translated: Vec<Range<u64>> = Vec::new();
-
-            let self_rng = self.source_range();
-
-            if rng.start < self.source_start {
-                let end = cmp::min(rng.end, self.source_start);
-                unmatched.push(rng.start..end);
-            }
-
-            if rng.end > self_rng.end {
-                let start = cmp::max(rng.start, self_rng.start);
-                unmatched.push(start..rng.end);
-            }
-
-            if self_rng.contains(&rng.start) || self_rng.contains(&(rng.end - 1)) {
-                let start = cmp::max(rng.start, self_rng.start);
-                let end = cmp::min(rng.end, self_rng.end);
-
-                matched_and_translated.push((start as i128 + self.convert_factor) as u64..(end as i128+self.convert_factor) as u64)
-            }
-
-            return (unmatched, matched_and_translated);
-        }
     }
 
     struct Section {
@@ -110,25 +85,13 @@ humidity-to-location map:
                 .find(|r| r.source_range().contains(source));
         }
 
-        fn map_source_range_to_destination(&self, input: &Range<u64>) -> Vec<Range<u64>> {
-            let mut unmatched = vec![input.to_owned()];
-            let mut results: Vec<Range<u64>> = vec![];
-
+        fn to_range_map(&self) -> RangeMap<u64> {
+            let mut map = RangeMap::new();
             for r in &self.ranges {
-                let mut new_unmatched: Vec<Range<u64>> = vec![];
-
-                for existing_unmatched in &unmatched {
-                    let (mut um, mut mt) = r.intersect(existing_unmatched);
-                    new_unmatched.append(&mut um);
-                    results.append(&mut mt);
-                }
-
-                unmatched = new_unmatched;
+                map.insert(r.source_range(), (r.source_start as i128 + r.convert_factor) as u64);
             }
 
-            results.append(&mut unmatched);
-
-            return results;
+            return map;
         }
     }
 
@@ -153,6 +116,49 @@ humidity-to-location map:
                 .map(|c| c[0].to_owned()..(c[0] + c[1]))
                 .collect_vec();
         }
+
+        fn sections(&self) -> [&Section; 7] {
+            return [
+                &self.seed_to_soil,
+                &self.soil_to_fertilizer,
+                &self.fertilizer_to_water,
+                &self.water_to_light,
+                &self.light_to_temperature,
+                &self.temperature_to_humidity,
+                &self.humidity_to_location,
+            ];
+        }
+
+        fn seed_to_location(&self) -> RangeMap<u64> {
+            return self.sections()
+                .iter()
+                .map(|s| s.to_range_map())
+                .reduce(|a, b| a.compose(&b))
+                .unwrap();
+        }
+    }
+
+    /// All seven sections folded into one seed -> location map, built once up front.
+    struct ComposedAlmanac {
+        seed_ranges: RangeSet<u64>,
+        seed_to_location: RangeMap<u64>,
+    }
+
+    impl ComposedAlmanac {
+        fn from_farm_data(farm_data: &FarmData) -> ComposedAlmanac {
+            return ComposedAlmanac {
+                seed_ranges: RangeSet::from_ranges(farm_data.seed_ranges()),
+                seed_to_location: farm_data.seed_to_location(),
+            };
+        }
+
+        fn min_location(&self) -> u64 {
+            return self.seed_to_location.min_image(&self.seed_ranges).unwrap();
+        }
+
+        fn seeds_for_locations(&self, locations: &RangeSet<u64>) -> RangeSet<u64> {
+            return self.seed_to_location.preimage(locations);
+        }
     }
 
     fn parse_file_into_areas(input: &str) -> Vec<&str> {
@@ -231,18 +237,10 @@ humidity-to-location map:
     }
 
     fn min_location_by_ranges(farm_data: &FarmData) -> u64 {
-        let seed_ranges = farm_data.seed_ranges();
+        let seed_ranges = RangeSet::from_ranges(farm_data.seed_ranges());
 
-        return seed_ranges
-            .iter()
-            .flat_map(|seed| farm_data.seed_to_soil.map_source_range_to_destination(seed))
-            .flat_map(|soil| farm_data.soil_to_fertilizer.map_source_range_to_destination(&soil))
-            .flat_map(|fert| farm_data.fertilizer_to_water.map_source_range_to_destination(&fert))
-            .flat_map(|water| farm_data.water_to_light.map_source_range_to_destination(&water))
-            .flat_map(|light| farm_data.light_to_temperature.map_source_range_to_destination(&light))
-            .flat_map(|temp| farm_data.temperature_to_humidity.map_source_range_to_destination(&temp))
-            .flat_map(|hum| farm_data.humidity_to_location.map_source_range_to_destination(&hum))
-            .map(|r| r.start)
+        return farm_data.seed_to_location()
+            .map_set(&seed_ranges)
             .min()
             .unwrap();
     }
@@ -267,6 +265,54 @@ humidity-to-location map:
         println!("Answer: {aaa:?}");
     }
 
+    #[test]
+    fn sample_composed_matches_chained() {
+        let farm_data = parse_farm_data(SAMPLE);
+        let composed = farm_data.seed_to_location();
+
+        for seed in 0..100 {
+            let chained = farm_data.sections()
+                .iter()
+                .fold(seed, |v, s| s.map_source_to_destination(&v));
+
+            assert_eq!(chained, composed.map(seed));
+        }
+
+        assert_eq!(35, min_location(&farm_data));
+        assert_eq!(46, min_location_by_ranges(&farm_data));
+    }
+
+    #[test]
+    fn sample_composed_almanac() {
+        let almanac = ComposedAlmanac::from_farm_data(&parse_farm_data(SAMPLE));
+
+        assert_eq!(46, almanac.min_location());
+
+        let seeds = almanac.seeds_for_locations(&RangeSet::from_ranges(iter::once(46..47)));
+        assert_eq!(1, seeds.count());
+        assert!(seeds.contains(82));
+
+        let low = RangeSet::from_ranges(iter::once(0..50));
+        let low_seeds = almanac.seeds_for_locations(&low);
+        for seed in 0..100 {
+            assert_eq!(low_seeds.contains(seed), low.contains(almanac.seed_to_location.map(seed)));
+        }
+    }
+
+    #[test]
+    fn seed_range_algebra() {
+        let a = RangeSet::from_ranges([79..93, 55..68]);
+        let b = RangeSet::from_ranges([60..85, 93..100]);
+
+        assert_eq!(RangeSet::from_ranges([55..70, 65..100]), a.union(&b));
+        assert_eq!(&[60..68, 79..85], a.intersection(&b).ranges());
+        assert_eq!(&[55..60, 85..93], a.difference(&b).ranges());
+        assert_eq!(&[45..58, 69..83], a.shift(-10).ranges());
+        assert_eq!(27, a.count());
+        assert!(a.contains(92) && !a.contains(93) && !a.is_empty());
+        assert!(RangeSet::<u64>::new().is_empty());
+    }
+
     #[test]
     fn part_1() {
         let data = read_data_file(5, "input.txt");
@@ -283,6 +329,7 @@ humidity-to-location map:
         let farm_data = parse_farm_data(&data);
 
         let aaa: u64 = min_location_by_ranges(&farm_data);
+        assert_eq!(aaa, ComposedAlmanac::from_farm_data(&farm_data).min_location());
 
         println!("Answer: {aaa:?}");
     }
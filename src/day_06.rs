@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod day06 {
     use itertools::Itertools;
+    use num::{BigInt, Signed, ToPrimitive, Zero};
+
+    use crate::read_data_file;
 
     #[derive(Debug)]
     struct RaceRecord {
@@ -8,24 +11,80 @@ mod day06 {
         distance: i64
     }
 
-    fn get_race_history() -> Vec<RaceRecord> {
-        return vec![
-            RaceRecord {time: 44, distance: 208},
-            RaceRecord {time: 80, distance: 1581},
-            RaceRecord {time: 65, distance: 1050},
-            RaceRecord {time: 72, distance: 1102},
-        ];
+    static SAMPLE: &str = r#"Time:      7  15   30
+Distance:  9  40  200"#;
+
+    fn parse_line_values(line: &str) -> Vec<i64> {
+        let (_, values) = line.split(':').collect_tuple().unwrap();
+
+        return values
+            .split_whitespace()
+            .map(|v| v.parse::<i64>().unwrap())
+            .collect_vec();
+    }
+
+    fn parse_kerned_value(line: &str) -> i64 {
+        let (_, values) = line.split(':').collect_tuple().unwrap();
+
+        return values
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .parse::<i64>()
+            .unwrap();
+    }
+
+    fn parse_races(input: &str) -> Vec<RaceRecord> {
+        let (times, distances) = input.lines().collect_tuple().unwrap();
+
+        return parse_line_values(times)
+            .into_iter()
+            .zip(parse_line_values(distances))
+            .map(|(time, distance)| RaceRecord { time, distance })
+            .collect_vec();
     }
 
-    fn get_sample() -> Vec<RaceRecord> {
-        return vec![
-            RaceRecord {time: 7, distance: 9},
-            RaceRecord {time: 15, distance: 40},
-            RaceRecord {time: 30, distance: 200},
-        ];
+    fn parse_kerned_race(input: &str) -> RaceRecord {
+        let (times, distances) = input.lines().collect_tuple().unwrap();
+
+        return RaceRecord {
+            time: parse_kerned_value(times),
+            distance: parse_kerned_value(distances),
+        };
     }
 
+    // holding for h wins when h * (time - h) > distance, i.e. h lies strictly between
+    // the roots of h^2 - time*h + distance. Worked in BigInt so i128 inputs can't overflow.
+    fn win_count_exact<T: Into<i128>>(time: T, distance: T) -> i128 {
+        let time = BigInt::from(time.into());
+        let distance = BigInt::from(distance.into());
 
+        let disc: BigInt = &time * &time - 4 * &distance;
+        if disc.is_negative() || disc.is_zero() {
+            return 0;
+        }
+
+        let wins = |h: &BigInt| {
+            let run_time: BigInt = &time - h;
+            return h * run_time > distance;
+        };
+
+        // the lower root lies in ((time - s - 1) / 2, (time - s) / 2], so at most two steps
+        let s = disc.sqrt();
+        let lower: BigInt = (&time - &s) / 2 - 1;
+        let mut lo = lower.max(BigInt::zero());
+        while &lo * 2 <= time && !wins(&lo) {
+            lo += 1;
+        }
+
+        if !wins(&lo) {
+            return 0;
+        }
+
+        let hi: BigInt = &time - &lo;
+        let count: BigInt = hi - lo + 1;
+        return count.to_i128().unwrap();
+    }
 
     fn calc_win_count(max_time: i64, max_distance: i64) -> i64 {
         let mut min_loss_time = max_time;
@@ -60,7 +119,7 @@ mod day06 {
         return input
         .iter()
             .map(|record| {
-                return calc_win_count(record.time, record.distance);
+                return win_count_exact(record.time, record.distance) as i64;
             })
             .product1()
             .unwrap();
@@ -68,28 +127,52 @@ mod day06 {
 
     #[test]
     fn sample_p1() {
-        let aaa: i64 = generate_part_1(&get_sample());
+        let aaa: i64 = generate_part_1(&parse_races(SAMPLE));
 
+        assert_eq!(288, aaa);
         println!("Answer: {aaa:?}");
     }
 
     #[test]
     fn sample_p2() {
-        let aaa: i64 = generate_part_1(&vec![RaceRecord{time: 71530, distance: 940200}]);
+        let aaa: i64 = generate_part_1(&vec![parse_kerned_race(SAMPLE)]);
 
+        assert_eq!(71503, aaa);
         println!("Answer: {aaa:?}");
     }
 
+    #[test]
+    fn exact_matches_scan() {
+        for time in 1..60i64 {
+            for distance in 0..(time * time / 4 + 2) {
+                let expected = (0..=time).filter(|h| h * (time - h) > distance).count() as i128;
+                assert_eq!(expected, win_count_exact(time, distance));
+            }
+        }
+
+        assert_eq!(calc_win_count(71530, 940200) as i128, win_count_exact(71530i64, 940200i64));
+    }
+
+    #[test]
+    fn exact_handles_i128() {
+        // time^2 overflows i128 here; h = 0 and h = time are the only losers
+        let time = i128::MAX / 2;
+        assert_eq!(time - 1, win_count_exact(time, 0i128));
+        assert_eq!(0, win_count_exact(4i128, 4i128));
+    }
+
     #[test]
     fn part_1() {
-        let aaa: i64 = generate_part_1(&get_race_history());
+        let data = read_data_file(6, "input.txt");
+        let aaa: i64 = generate_part_1(&parse_races(&data));
 
         println!("Answer: {aaa:?}");
     }
 
     #[test]
     fn part_2() {
-        let aaa: i64 = generate_part_1(&vec![RaceRecord{time: 44806572, distance: 208158110501102}]);
+        let data = read_data_file(6, "input.txt");
+        let aaa: i64 = generate_part_1(&vec![parse_kerned_race(&data)]);
 
         println!("Answer: {aaa:?}");
     }
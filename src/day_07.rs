@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod day07 {
     use std::cmp::Ordering;
-    use std::collections::HashMap;
+    use std::collections::HashSet;
 
     use itertools::Itertools;
 
@@ -14,6 +14,105 @@ KTJJT 220
 QQQJA 483"#;
 
 
+    enum Pattern {
+        // group sizes that must all be present, e.g. [3, 2] for a full house
+        Groups(Vec<usize>),
+        // every card distinct and consecutive in card order
+        Straight,
+        HighCard,
+    }
+
+    struct HandCategory {
+        name: String,
+        pattern: Pattern,
+    }
+
+    impl HandCategory {
+        fn new(name: &str, pattern: Pattern) -> HandCategory {
+            return HandCategory {
+                name: name.to_owned(),
+                pattern,
+            };
+        }
+    }
+
+    struct HandRules {
+        card_order: Vec<char>,
+        wildcards: HashSet<char>,
+        // weakest first
+        categories: Vec<HandCategory>,
+    }
+
+    impl HandRules {
+        fn standard() -> HandRules {
+            return HandRules {
+                card_order: "23456789TJQKA".chars().collect_vec(),
+                wildcards: HashSet::new(),
+                categories: vec![
+                    HandCategory::new("HIGH", Pattern::HighCard),
+                    HandCategory::new("PAIR", Pattern::Groups(vec![2])),
+                    HandCategory::new("TWO_PAIR", Pattern::Groups(vec![2, 2])),
+                    HandCategory::new("THREE", Pattern::Groups(vec![3])),
+                    HandCategory::new("FULL", Pattern::Groups(vec![3, 2])),
+                    HandCategory::new("FOUR", Pattern::Groups(vec![4])),
+                    HandCategory::new("FIVE", Pattern::Groups(vec![5])),
+                ],
+            };
+        }
+
+        fn jokers() -> HandRules {
+            return HandRules {
+                card_order: "J23456789TQKA".chars().collect_vec(),
+                wildcards: HashSet::from(['J']),
+                ..HandRules::standard()
+            };
+        }
+
+        fn category_score(&self, name: &str) -> usize {
+            return self.categories.iter().position(|c| c.name == name).unwrap() + 1;
+        }
+
+        fn strength(&self, c: char) -> usize {
+            return self.card_order.iter().position(|o| *o == c).unwrap() + 1;
+        }
+
+        fn score_hand(&self, hand: &str) -> usize {
+            let wild_count = hand.chars().filter(|c| self.wildcards.contains(c)).count();
+            let natural = hand.chars().filter(|c| !self.wildcards.contains(c)).collect_vec();
+
+            let counts = natural.iter().counts().into_values().sorted().rev().collect_vec();
+
+            let matched = self.categories
+                .iter()
+                .rposition(|cat| match &cat.pattern {
+                    Pattern::Groups(sizes) => {
+                        // fill the biggest groups first; wildcards cover whatever is missing
+                        let missing: usize = sizes.iter()
+                            .enumerate()
+                            .map(|(i, s)| s.saturating_sub(*counts.get(i).unwrap_or(&0)))
+                            .sum();
+
+                        missing <= wild_count
+                    }
+                    Pattern::Straight => {
+                        let ranks = natural.iter().map(|c| self.strength(*c)).sorted().collect_vec();
+                        counts.iter().all(|c| *c == 1)
+                            && ranks.last().zip(ranks.first()).map(|(hi, lo)| hi - lo < hand.len()).unwrap_or(true)
+                    }
+                    Pattern::HighCard => true
+                })
+                .unwrap();
+
+            return matched + 1;
+        }
+
+        fn tie_vector(&self, hand: &str) -> Vec<usize> {
+            return hand.chars()
+                .map(|c| self.strength(c))
+                .collect_vec();
+        }
+    }
+
     struct Hand {
         _original: String,
         score_type: usize,
@@ -47,236 +146,59 @@ QQQJA 483"#;
     }
 
     impl Hand {
-        fn from_str(s: &str) -> Hand {
+        fn from_str(s: &str, rules: &HandRules) -> Hand {
             let (original, wager_str) = s.split(" ").collect_tuple().unwrap();
 
             return Hand {
                 _original: original.to_owned(),
-                score_type: score_hand(original),
-                tie_vector: tie_vector(original),
-                wager: wager_str.parse::<usize>().unwrap(),
-            };
-        }
-
-        fn from_str_p2(s: &str) -> Hand {
-            let (original, wager_str) = s.split(" ").collect_tuple().unwrap();
-
-            return Hand {
-                _original: original.to_owned(),
-                score_type: score_hand_p2(original),
-                tie_vector: tie_vector_p2(original),
+                score_type: rules.score_hand(original),
+                tie_vector: rules.tie_vector(original),
                 wager: wager_str.parse::<usize>().unwrap(),
             };
         }
     }
 
-    fn score_hand(hand: &str) -> usize {
-        let groups = hand
-            .chars()
-            .fold(HashMap::<char, usize>::new(), |mut acc, c| {
-                if acc.contains_key(&c) {
-                    acc.insert(c, acc[&c] + 1);
-                } else {
-                    acc.insert(c, 1);
-                }
-
-                acc
-            });
-
-        let counts = groups.values().sorted().rev().collect_vec();
-        let hand_types = build_hand_types_map();
-
-        if counts.len() == 1 && *counts[0] == 5 {
-            return hand_types["FIVE"];
-        }
-
-        if counts.len() == 2 && *counts[0] == 4 {
-            return hand_types["FOUR"];
-        }
-
-        if counts.len() == 2 && *counts[0] == 3 {
-            return hand_types["FULL"];
-        }
-
-        if counts.len() == 3 && *counts[0] == 3 {
-            return hand_types["THREE"];
-        }
-
-        if counts.len() == 3 && *counts[0] == 2 && *counts[1] == 2 {
-            return hand_types["TWO_PAIR"];
-        }
-
-        if counts.len() == 4 && *counts[0] == 2 {
-            return hand_types["PAIR"];
-        }
-
-        return hand_types["HIGH"];
-    }
-
-    fn score_hand_p2(hand: &str) -> usize {
-        let groups = hand
-            .chars()
-            .fold(HashMap::<char, usize>::new(), |mut acc, c| {
-                if acc.contains_key(&c) {
-                    acc.insert(c, acc[&c] + 1);
-                } else {
-                    acc.insert(c, 1);
-                }
-
-                acc
-            });
-
-        let joker_count: usize = if groups.contains_key(&'J') { groups[&'J'] } else { 0usize };
-
-        let counts = groups.into_iter().filter(|(k, _v)| *k != 'J').map(|(_k, v)| v).sorted().rev().collect_vec();
-        let hand_types = build_hand_types_map();
-
-        if joker_count == 5 {
-            return hand_types["FIVE"];
-        }
-
-        if counts.len() == 1 && (counts[0] + joker_count) == 5 {
-            return hand_types["FIVE"];
-        }
-
-        if counts.len() == 2 && (counts[0] + joker_count) == 4 {
-            return hand_types["FOUR"];
-        }
-
-        if counts.len() == 2 {
-            return hand_types["FULL"];
-        }
-
-
-        if counts.len() == 3 && (counts[0] + joker_count) == 3 {
-            return hand_types["THREE"];
-        }
-
-        if counts.len() == 3 {
-            return hand_types["TWO_PAIR"];
-        }
-
-        if counts.len() == 4 && (counts[0] + joker_count) == 2 {
-            return hand_types["PAIR"];
-        }
-
-        return hand_types["HIGH"];
-    }
-
-    fn build_strength_map() -> HashMap<char, usize> {
-        HashMap::from([
-            ('2', 1),
-            ('3', 2),
-            ('4', 3),
-            ('5', 4),
-            ('6', 5),
-            ('7', 6),
-            ('8', 7),
-            ('9', 8),
-            ('T', 9),
-            ('J', 10),
-            ('Q', 11),
-            ('K', 12),
-            ('A', 13),
-        ])
-    }
-
-    fn build_strength_map_p2() -> HashMap<char, usize> {
-        HashMap::from([
-            ('J', 1),
-            ('2', 2),
-            ('3', 3),
-            ('4', 4),
-            ('5', 5),
-            ('6', 6),
-            ('7', 7),
-            ('8', 8),
-            ('9', 9),
-            ('T', 10),
-            ('Q', 11),
-            ('K', 12),
-            ('A', 13),
-        ])
-    }
-
-    fn build_hand_types_map() -> HashMap<String, usize> {
-        HashMap::from([
-            ("HIGH".to_owned(), 1),
-            ("PAIR".to_owned(), 2),
-            ("TWO_PAIR".to_owned(), 3),
-            ("THREE".to_owned(), 4),
-            ("FULL".to_owned(), 5),
-            ("FOUR".to_owned(), 6),
-            ("FIVE".to_owned(), 7),
-        ])
-    }
-
-    fn tie_vector(hand: &str) -> Vec<usize> {
-        let strength = build_strength_map();
-        hand.chars()
-            .map(|c| strength[&c])
-            .collect_vec()
-    }
-
-    fn tie_vector_p2(hand: &str) -> Vec<usize> {
-        let strength = build_strength_map_p2();
-        hand.chars()
-            .map(|c| strength[&c])
-            .collect_vec()
-    }
-
-    fn parse_input(input: &str) -> Vec<Hand> {
+    fn parse_input(input: &str, rules: &HandRules) -> Vec<Hand> {
         input
             .lines()
-            .map(|l| Hand::from_str(l))
+            .map(|l| Hand::from_str(l, rules))
             .collect_vec()
     }
 
-    fn parse_input_p2(input: &str) -> Vec<Hand> {
-        input
-            .lines()
-            .map(|l| Hand::from_str_p2(l))
-            .collect_vec()
+    fn total_winnings(input: &str, rules: &HandRules) -> usize {
+        return parse_input(input, rules)
+            .into_iter()
+            .sorted()
+            .enumerate()
+            .map(|(i, h)| (i + 1) * h.wager)
+            .sum();
     }
 
     #[test]
     fn sample_p1() {
         let data = SAMPLE;
-        let hands = parse_input(data).into_iter().sorted().collect_vec();
 
-        let aaa: usize = hands.iter()
-            .enumerate()
-            .map(|(i, h)| (i + 1) * h.wager)
-            .sum1()
-            .unwrap();
+        let aaa: usize = total_winnings(data, &HandRules::standard());
 
+        assert_eq!(6440, aaa);
         println!("Answer: {aaa:?}");
     }
 
     #[test]
     fn sample_p2() {
         let data = SAMPLE;
-        let hands = parse_input_p2(data).into_iter().sorted().collect_vec();
 
-        let aaa: usize = hands.iter()
-            .enumerate()
-            .map(|(i, h)| (i + 1) * h.wager)
-            .sum1()
-            .unwrap();
+        let aaa: usize = total_winnings(data, &HandRules::jokers());
 
+        assert_eq!(5905, aaa);
         println!("Answer: {aaa:?}");
     }
 
     #[test]
     fn part_1() {
         let data = read_data_file(7, "input.txt");
-        let hands = parse_input(&data).into_iter().sorted().collect_vec();
 
-        let aaa: usize = hands.iter()
-            .enumerate()
-            .map(|(i, h)| (i + 1) * h.wager)
-            .sum1()
-            .unwrap();
+        let aaa: usize = total_winnings(&data, &HandRules::standard());
 
         println!("Answer: {aaa:?}");
     }
@@ -284,23 +206,37 @@ QQQJA 483"#;
     #[test]
     fn part_2() {
         let data = read_data_file(7, "input.txt");
-        let hands = parse_input_p2(&data).into_iter().sorted().collect_vec();
 
-        let aaa: usize = hands.iter()
-            .enumerate()
-            .map(|(i, h)| (i + 1) * h.wager)
-            .sum1()
-            .unwrap();
+        let aaa: usize = total_winnings(&data, &HandRules::jokers());
 
         println!("Answer: {aaa:?}");
     }
 
     #[test]
     fn p2_score_hand_tests() {
-        let ht = build_hand_types_map();
-        assert_eq!(ht["FULL"], score_hand_p2("J3322"));
-        assert_eq!(ht["THREE"], score_hand_p2("J3321"));
-        assert_eq!(ht["TWO_PAIR"], score_hand_p2("21321"));
-        assert_eq!(ht["FIVE"], score_hand_p2("JJJJJ"));
+        let rules = HandRules::jokers();
+        assert_eq!(rules.category_score("FULL"), rules.score_hand("J3322"));
+        assert_eq!(rules.category_score("THREE"), rules.score_hand("J3321"));
+        assert_eq!(rules.category_score("TWO_PAIR"), rules.score_hand("21321"));
+        assert_eq!(rules.category_score("FIVE"), rules.score_hand("JJJJJ"));
+    }
+
+    #[test]
+    fn custom_rules_score_hand_tests() {
+        let mut categories = HandRules::standard().categories;
+        categories.insert(4, HandCategory::new("STRAIGHT", Pattern::Straight));
+
+        let rules = HandRules {
+            card_order: "23456789TJQKA".chars().collect_vec(),
+            wildcards: HashSet::from(['2', 'J']),
+            categories,
+        };
+
+        assert_eq!(rules.category_score("STRAIGHT"), rules.score_hand("TJQKA"));
+        assert_eq!(rules.category_score("STRAIGHT"), rules.score_hand("3457J"));
+        assert_eq!(rules.category_score("FOUR"), rules.score_hand("2J3K3"));
+        assert_eq!(rules.category_score("FULL"), rules.score_hand("KKQQJ"));
+        assert_eq!(rules.category_score("PAIR"), rules.score_hand("3459J"));
+        assert_eq!(rules.category_score("HIGH"), HandRules::standard().score_hand("3459A"));
     }
 }
\ No newline at end of file
@@ -1,11 +1,9 @@
 #[cfg(test)]
 mod day10 {
-    use std::collections::{HashMap, HashSet};
-    use std::collections::hash_map::RandomState;
-
     use itertools::Itertools;
 
     use crate::grid::{Grid, Point};
+    use crate::pipe_maze::PipeMaze;
     use crate::read_data_file;
 
     static SAMPLE_1: &str = r#"-L|F7
@@ -58,100 +56,29 @@ L---JF-JLJ.||-FJLJJ7
 L.L7LFJ|||||FJL7||LJ
 L7JLJL-JLJLJL--JLJ.L"#;
 
-    fn build_move_index() -> HashMap<char, Vec<Vec<i32>>> {
-        let north = vec![0, -1];
-        let east = vec![1, 0];
-        let south = vec![0, 1];
-        let west = vec![-1, 0];
-
-        return HashMap::from([
-            ('|', vec![north.to_owned(), south.to_owned()]),
-            ('-', vec![east.to_owned(), west.to_owned()]),
-            ('L', vec![north.to_owned(), east.to_owned()]),
-            ('J', vec![north.to_owned(), west.to_owned()]),
-            ('7', vec![south.to_owned(), west.to_owned()]),
-            ('F', vec![south.to_owned(), east.to_owned()]),
-            ('.', vec![]),
-        ]);
-    }
-
-    fn follow_path(grid: &Grid, start: &Point) -> Vec<Point> {
-        let move_index = build_move_index();
-        let starting_tile = grid.get_point(start).unwrap();
-        let starting_moves = move_index.get(starting_tile).unwrap();
-        let starting_move = start.apply_dir_vector(&starting_moves[0]).unwrap();
-
-        let mut path: Vec<Point> = vec![start.to_owned(), starting_move.to_owned()];
-
-        while path.first().unwrap() != path.last().unwrap() {
-            let current = path.get(path.len() - 1).unwrap();
-            let prev = path.get(path.len() - 2).unwrap();
-            let current_tile = grid.get_point(current).unwrap();
-            let moves = move_index.get(current_tile).unwrap();
-            let mv = moves.iter()
-                .map(|m| current.apply_dir_vector(m).unwrap())
-                .filter(|new| *new != *prev)
-                .exactly_one()
-                .unwrap();
-
-            path.push(mv);
-        }
-
-        return path;
+    fn mark_start(data: &str, start: &Point) -> String {
+        return data
+            .lines()
+            .enumerate()
+            .map(|(y, l)| {
+                l.chars()
+                    .enumerate()
+                    .map(|(x, c)| if x == start.x && y == start.y { 'S' } else { c })
+                    .collect::<String>()
+            })
+            .join("\n");
     }
 
-    fn count_inside(grid: &Grid, path: &Vec<Point>) -> usize {
-        let path_points: HashSet<Point, RandomState> = HashSet::from_iter(path.iter().cloned());
-
-        let mut inside_points: Vec<Point> = vec![];
-        let mut inside_count = 0usize;
-
-
-        let (x_range, y_range) = grid.bounds();
-
-        for y in y_range.to_owned() {
-            for x in x_range.to_owned() {
-                let p = Point::new(x as i32, y as i32);
-                if !path_points.contains(&p) {
-                    let mut count = 0;
-
-                    let mut xx = p.x;
-                    let mut yy = p.y;
-
-                    while xx < x_range.end && yy < y_range.end {
-                        let c = grid.get(xx, yy).unwrap();
-                        let pp = Point::new(xx as i32,yy as i32);
-
-                        if path_points.contains(&pp) && *c != 'L' && *c != '7' {
-                            count = count + 1;
-                        }
-
-                        xx = xx + 1;
-                        yy = yy + 1;
-                    }
-
-
-                    if count % 2 == 1 {
-                        inside_count = inside_count + 1;
-                        inside_points.push(p);
-                    }
-                }
-            }
-        }
-
-        return inside_count;
-    }
-
-
     #[test]
     fn sample_1_p1() {
         let data = SAMPLE_1;
         let start = Point::new(1, 1);
         let grid = Grid::from_lines(data);
+        let maze = PipeMaze::with_start(grid, start).unwrap();
 
-        let path = follow_path(&grid, &start);
+        let results: usize = maze.find_loop().len() / 2;
 
-        let results: usize = path.len() / 2;
+        assert_eq!(4, results);
 
         println!("Answer: {results:?}");
     }
@@ -161,10 +88,11 @@ L7JLJL-JLJLJL--JLJ.L"#;
         let data = SAMPLE_2;
         let start = Point::new(0, 2);
         let grid = Grid::from_lines(data);
+        let maze = PipeMaze::with_start(grid, start).unwrap();
 
-        let path = follow_path(&grid, &start);
+        let results: usize = maze.find_loop().len() / 2;
 
-        let results: usize = path.len() / 2;
+        assert_eq!(8, results);
 
         println!("Answer: {results:?}");
     }
@@ -174,10 +102,12 @@ L7JLJL-JLJLJL--JLJ.L"#;
         let data = SAMPLE_3;
         let start = Point::new(1, 1);
         let grid = Grid::from_lines(data);
+        let maze = PipeMaze::with_start(grid, start).unwrap();
 
-        let path = follow_path(&grid, &start);
+        let results = maze.count_enclosed();
 
-        let results = count_inside(&grid, &path);
+        assert_eq!(4, results);
+        assert_eq!(results, maze.count_enclosed_shoelace());
 
         println!("Answer: {results:?}");
     }
@@ -187,10 +117,12 @@ L7JLJL-JLJLJL--JLJ.L"#;
         let data = SAMPLE_4;
         let start = Point::new(1, 1);
         let grid = Grid::from_lines(data);
+        let maze = PipeMaze::with_start(grid, start).unwrap();
 
-        let path = follow_path(&grid, &start);
+        let results = maze.count_enclosed();
 
-        let results = count_inside(&grid, &path);
+        assert_eq!(4, results);
+        assert_eq!(results, maze.count_enclosed_shoelace());
 
         println!("Answer: {results:?}");
     }
@@ -200,10 +132,12 @@ L7JLJL-JLJLJL--JLJ.L"#;
         let data = SAMPLE_5;
         let start = Point::new(12, 4);
         let grid = Grid::from_lines(data);
+        let maze = PipeMaze::with_start(grid, start).unwrap();
 
-        let path = follow_path(&grid, &start);
+        let results = maze.count_enclosed();
 
-        let results = count_inside(&grid, &path);
+        assert_eq!(8, results);
+        assert_eq!(results, maze.count_enclosed_shoelace());
 
         println!("Answer: {results:?}");
     }
@@ -213,36 +147,49 @@ L7JLJL-JLJLJL--JLJ.L"#;
         let data = SAMPLE_6;
         let start = Point::new(4, 0);
         let grid = Grid::from_lines(data);
+        let maze = PipeMaze::with_start(grid, start).unwrap();
 
-        let path = follow_path(&grid, &start);
+        let results = maze.count_enclosed();
 
-        let results = count_inside(&grid, &path);
+        assert_eq!(10, results);
+        assert_eq!(results, maze.count_enclosed_shoelace());
 
         println!("Answer: {results:?}");
     }
 
     #[test]
-    fn part_1() {
-        let data = read_data_file(10, "input-fixed.txt");
-        let start = Point::new(34, 114);
-        let grid = Grid::from_lines(&data);
+    fn infers_start_tile() {
+        for (data, start) in [(SAMPLE_1, Point::new(1, 1)), (SAMPLE_2, Point::new(0, 2)), (SAMPLE_5, Point::new(12, 4)), (SAMPLE_6, Point::new(4, 0))] {
+            let original = *Grid::from_lines(data).get_point(&start).unwrap();
+            let maze = PipeMaze::from_lines(&mark_start(data, &start)).unwrap();
 
-        let path = follow_path(&grid, &start);
+            assert_eq!(original, maze.start_tile());
+        }
+    }
 
-        let results: usize = path.len() / 2;
+    #[test]
+    fn infers_start_tile_with_extra_neighbours() {
+        // the pipe west of S points at it too, so `-` and `7` fit as well as `F`
+        let maze = PipeMaze::from_lines(".....\n-S-7.\n.|.|.\n.L-J.\n.....").unwrap();
+
+        assert_eq!('F', maze.start_tile());
+        assert_eq!(8, maze.find_loop().len());
+    }
+
+    #[test]
+    fn part_1() {
+        let maze = PipeMaze::from_lines(&read_data_file(10, "input.txt")).unwrap();
+
+        let results: usize = maze.find_loop().len() / 2;
 
         println!("Answer: {results:?}");
     }
 
     #[test]
     fn part_2() {
-        let data = read_data_file(10, "input-fixed.txt");
-        let start = Point::new(34, 114);
-        let grid = Grid::from_lines(&data);
-
-        let path = follow_path(&grid, &start);
+        let maze = PipeMaze::from_lines(&read_data_file(10, "input.txt")).unwrap();
 
-        let results = count_inside(&grid, &path);
+        let results = maze.count_enclosed();
 
         println!("Answer: {results:?}");
     }
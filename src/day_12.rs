@@ -1,9 +1,8 @@
 #[cfg(test)]
 mod day12 {
-    use std::collections::HashMap;
-
     use itertools::Itertools;
 
+    use crate::nonogram::{arrangements, count_arrangements, nth_arrangement, solve_line, solve_nonogram};
     use crate::read_data_file;
 
     static SAMPLE_1: &str = r#"???.### 1,1,3
@@ -47,42 +46,6 @@ mod day12 {
         return x + y;
     }
 
-    fn count_solutions_better(data: &str, runs: &[usize], cache: &mut HashMap<(String, String), usize>) -> usize {
-        let key: (String, String) = (data.to_owned(), runs.iter().join(","));
-        if cache.contains_key(&key) {
-            return cache[&key];
-        }
-
-        let stripped = data.trim_start_matches(".");
-
-        let answer: usize = if stripped.is_empty() {
-            let a = if runs.len() == 0 { 1 } else { 0 };
-            a
-        } else if runs.is_empty() {
-            let a = if stripped.contains('#') { 0 } else { 1 };
-            a
-        } else if stripped.chars().nth(0).unwrap() == '#' {
-            let a = if stripped.len() < runs[0] || stripped[0..runs[0]].contains('.') {
-                0 // not enough space
-            } else if stripped.len() == runs[0] {
-                let a = if runs.len() == 1 { 1usize } else { 0usize }; //perfect match
-                a
-            } else if stripped.chars().nth(runs[0]).unwrap_or('!') == '#' {
-                0  // too many springs
-            } else {
-                count_solutions_better(&stripped[runs[0] + 1..], &runs[1..], cache)
-            };
-            a
-        } else {
-            let aa = count_solutions_better(&("#".to_owned() + &stripped[1..]), runs, cache);
-            let bb = count_solutions_better(&stripped[1..], runs, cache);
-            aa + bb
-        };
-
-        cache.insert(key, answer);
-        return answer;
-    }
-
     fn parse_line(input: &str) -> (&str, Vec<usize>) {
         let split = input.split(" ").collect_vec();
 
@@ -103,7 +66,7 @@ mod day12 {
 
     fn apply_p1(input: &str) -> usize {
         return parse_lines(input)
-            .iter().map(|(data, parity)| count_solutions(data, parity))
+            .iter().map(|(data, parity)| count_arrangements(data, parity))
             .sum();
     }
 
@@ -111,7 +74,7 @@ mod day12 {
         return parse_lines(input)
             .iter()
             .map(|(data, parity)| expand(data, parity))
-            .map(|(data, parity)| count_solutions_better(&data, &parity, &mut HashMap::new()))
+            .map(|(data, parity)| count_arrangements(&data, &parity))
             .sum();
     }
 
@@ -136,7 +99,7 @@ mod day12 {
 
     fn run(d: &str, v: &Vec<usize>) -> usize {
         let (dd, vv) = expand(d, v);
-        return count_solutions_better(&dd, &vv, &mut HashMap::new());
+        return count_arrangements(&dd, &vv);
     }
 
     #[test]
@@ -149,6 +112,40 @@ mod day12 {
         assert_eq!(506250, run("?###????????", &vec![3, 2, 1]));
     }
 
+    #[test]
+    fn test_arrangements() {
+        let all = arrangements("?###????????", &[3, 2, 1]);
+        assert_eq!(10, all.len());
+        assert!(all.iter().all(|a| is_correct(a, &vec![3, 2, 1])));
+        assert_eq!(all.len(), all.iter().unique().count());
+
+        assert_eq!(Some(".###.##.#...".to_owned()), nth_arrangement("?###????????", &[3, 2, 1], 9));
+        assert_eq!(None, nth_arrangement("?###????????", &[3, 2, 1], 10));
+
+        for (data, parity) in parse_lines(SAMPLE_1) {
+            assert_eq!(count_solutions(data, &parity), count_arrangements(data, &parity));
+        }
+    }
+
+    #[test]
+    fn test_solve_line() {
+        assert_eq!(Some("#.#.###".to_owned()), solve_line("???.###", &[1, 1, 3]));
+        assert_eq!(Some(".###.???????".to_owned()), solve_line("?###????????", &[3, 2, 1]));
+        assert_eq!(Some("??###??".to_owned()), solve_line("???????", &[5]));
+        assert_eq!(None, solve_line("#.#", &[3]));
+    }
+
+    #[test]
+    fn test_solve_nonogram() {
+        let rows = vec![vec![3], vec![1, 1], vec![5], vec![1, 1], vec![1, 1]];
+        let cols = vec![vec![4], vec![1, 1], vec![1, 1], vec![1, 1], vec![4]];
+
+        let grid = solve_nonogram(&rows, &cols).unwrap();
+        assert_eq!(".###.\n#...#\n#####\n#...#\n#...#", grid.print_string());
+
+        assert!(solve_nonogram(&[vec![2]], &[vec![1]]).is_none());
+    }
+
     #[test]
     fn test_is_correct() {
         assert_eq!(true, is_correct(".###.##.#...", &vec![3, 2, 1]));
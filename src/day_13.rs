@@ -2,7 +2,8 @@
 mod day13 {
     use std::ops::Range;
     use itertools::Itertools;
-    use crate::grid::Grid;
+    use crate::grid::{Grid, Point};
+    use crate::reflection::{Axis, reflections_with_smudges};
 
     use crate::read_data_file;
 
@@ -151,50 +152,12 @@ mod day13 {
         return 0;
     }
 
-    fn get_sym_value_p2(grid: &mut Grid) -> usize {
-        let vr = find_vertical_mirror_line(grid, None).map(|x| x - 1);
-        let hr = find_horizontal_mirror_line(grid, None).map(|x| x - 1);
-
-        let (x_bounds, y_bounds) = grid.bounds();
-
-        let mut prev_smudge: Option<(usize, usize)> = None;
-
-        for y in y_bounds.to_owned() {
-            for x in x_bounds.to_owned() {
-                if prev_smudge.is_some() {
-                    let (xx,yy) = prev_smudge.unwrap();
-                    flip(grid, xx, yy);
-                }
-
-                prev_smudge = Some((x,y));
-                flip(grid, x, y);
-
-                let vrr = find_vertical_mirror_line(grid, vr);
-                if vrr.is_some() {
-                    return vrr.unwrap();
-                }
-
-                let hrr = find_horizontal_mirror_line(grid, hr);
-                if hrr.is_some() {
-                    return hrr.unwrap() * 100;
-                }
-            }
-        }
-
-        return 0;
-    }
-
-    fn flip(grid: &mut Grid, x: usize, y: usize) {
-        let c = *grid.get(x,y).unwrap();
-
-        if c == '.' {
-            grid.set(x,y, '#');
-        }
-
-        if c == '#' {
-            grid.set(x,y, '.');
-        }
-
+    fn get_sym_value_p2(grid: &Grid) -> usize {
+        return reflections_with_smudges(grid, 1)
+            .iter()
+            .map(|r| r.summary())
+            .exactly_one()
+            .unwrap();
     }
 
     fn apply_p1(data: &str) -> usize {
@@ -214,9 +177,9 @@ mod day13 {
             .split("\n\n")
             .map(|pan| Grid::from_lines(pan))
             .enumerate()
-            .map(|(idx, mut g)| {
+            .map(|(idx, g)| {
                 println!("Grid: {idx}");
-                get_sym_value_p2(&mut g)
+                get_sym_value_p2(&g)
             })
             .sum()
     }
@@ -249,11 +212,32 @@ mod day13 {
         assert_eq!(Some(4), answer2);
     }
 
+    #[test]
+    fn test_reflections_with_smudges() {
+        let data = SAMPLE_1;
+        let panels = data.split("\n\n").map(Grid::from_lines).collect_vec();
+
+        for g in &panels {
+            let clean = reflections_with_smudges(g, 0);
+            assert_eq!(vec![get_sym_value(g)], clean.iter().map(|r| r.summary()).collect_vec());
+        }
+
+        let smudged = reflections_with_smudges(&panels[0], 1);
+        assert_eq!(1, smudged.len());
+        assert_eq!(Axis::Horizontal, smudged[0].axis);
+        assert_eq!(3, smudged[0].position);
+        assert_eq!(vec![(Point::new(0, 0), Point::new(0, 5))], smudged[0].smudges);
+
+        let smudged2 = reflections_with_smudges(&panels[1], 1);
+        assert_eq!(vec![(Point::new(4, 0), Point::new(4, 1))], smudged2[0].smudges);
+    }
+
     #[test]
     fn sample_1_p1() {
         let data = SAMPLE_1;
 
         let results = apply_p1(data);
+        assert_eq!(405, results);
         println!("Answer: {results:?}");
     }
 
@@ -262,6 +246,7 @@ mod day13 {
         let data = SAMPLE_1;
 
         let results = apply_p2(data);
+        assert_eq!(400, results);
         println!("Answer: {results:?}");
     }
 
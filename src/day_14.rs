@@ -1,9 +1,12 @@
 #[cfg(test)]
 mod day14 {
     use itertools::Itertools;
+    use crate::cycle::{brent, brent_state_at, find_cycle};
     use crate::grid::{DIR, Grid, Point};
     use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+    use crate::grid_mask::GridMask;
     use crate::read_data_file;
+    use crate::rock_platform::Platform;
 
     static SAMPLE_1: &str = r#"O....#....
 O.OO#....#
@@ -84,11 +87,11 @@ O.#..O.#.#
         return total as usize;
     }
 
-    fn apply_p1(grid: &mut Grid) -> usize {
-        lean_grid(grid, &NORTH);
-        grid.print();
+    fn apply_p1(data: &str) -> usize {
+        let mut platform = Platform::from_lines(data);
+        platform.tilt(&NORTH);
 
-        return calculate_load(grid, &NORTH);
+        return platform.load(&NORTH);
     }
 
     fn run_cycle(grid: &mut Grid) {
@@ -102,72 +105,133 @@ O.#..O.#.#
     fn sample_1_p1() {
         let data = SAMPLE_1;
 
-        let mut grid = Grid::from_lines(data);
-        let results = apply_p1(&mut grid);
+        let results = apply_p1(data);
+        assert_eq!(136, results);
         println!("Answer: {results:?}");
     }
 
     #[test]
     fn sample_1_p2() {
         let data = SAMPLE_1;
-        let mut grid = Grid::from_lines(&data);
 
+        let results = apply_p2(data);
+        assert_eq!(64, results);
+        println!("Answer: {results:?}");
+    }
 
+    fn spin(state: &String) -> String {
+        let mut grid = Grid::from_lines(state);
+        run_cycle(&mut grid);
+        return grid.print_string();
+    }
 
-        let results = apply_p2(&mut grid);
-        println!("Answer: {results:?}");
+    fn apply_p2(data: &str) -> usize {
+        return Platform::from_lines(data)
+            .state_after(1000000000)
+            .load(&NORTH);
     }
 
-    fn apply_p2(mut grid: &mut Grid) -> usize {
-        let (offset, cycle_size, storage) = find_cycle(&mut grid).unwrap();
+    #[test]
+    fn platform_matches_lean_grid() {
+        for dir in [NORTH, SOUTH, EAST, WEST] {
+            let mut grid = Grid::from_lines(SAMPLE_1);
+            lean_grid(&mut grid, &dir);
+
+            let mut platform = Platform::from_lines(SAMPLE_1);
+            platform.tilt(&dir);
+            assert_eq!(grid.print_string(), platform.print_string());
+        }
 
-        let total_cycles = 1000000000usize;
+        let mut grid = Grid::from_lines(SAMPLE_1);
+        let start = Platform::from_lines(SAMPLE_1);
+        let mut platform = start.clone();
+        for n in 1..=3 {
+            run_cycle(&mut grid);
+            platform.spin_cycle();
+            assert_eq!(grid.print_string(), platform.print_string());
+            assert_eq!(grid.print_string(), start.state_after(n).print_string());
+        }
+    }
 
-        let remainder = (total_cycles - offset) % cycle_size;
+    #[test]
+    fn platform_non_square() {
+        let mut platform = Platform::from_lines("..O.#O\nO.#..O\n.O..O.");
+        assert_eq!(6, platform.round_rocks().len());
 
-        let x = &storage[offset - 1 + remainder];
-        let new_grid = Grid::from_lines(x);
+        platform.tilt(&EAST);
+        assert_eq!("...O#O\n.O#..O\n....OO", platform.print_string());
+        assert_eq!(29, platform.load(&EAST));
 
-        return calculate_load(&new_grid, &NORTH);
+        platform.tilt(&NORTH);
+        assert_eq!(".O.O#O\n..#.OO\n.....O", platform.print_string());
+        assert_eq!(14, platform.load(&NORTH));
     }
 
-    fn find_cycle(mut grid: &mut &mut Grid) -> Option<(usize, usize, Vec<String>)> {
-        let mut storage: Vec<String> = vec![];
+    fn north_load(rocks: &GridMask) -> usize {
+        let height = rocks.rows.len();
+        return rocks.rows
+            .iter()
+            .enumerate()
+            .map(|(y, row)| row.count_ones() as usize * (height - y))
+            .sum();
+    }
 
-        for i in 0..1000 {
-            run_cycle(&mut grid);
-            let cycle_res = grid.print_string();
-
-            let pos = storage.iter().position(|v| *v == cycle_res);
-            if pos.is_some() {
-                let first = pos.unwrap();
-                let dif = i - first;
-                println!("first: {first}, i: {i}, diff: {dif}");
-                return Some((first + 1, dif, storage))
-            }
+    fn spin_masked(rocks: &GridMask, cubes: &GridMask) -> GridMask {
+        return [NORTH, WEST, SOUTH, EAST].iter().fold(rocks.clone(), |r, d| r.tilt(cubes, d));
+    }
+
+    /// Cycle detection keyed on the round rock bitmask, tilting the masks themselves.
+    fn apply_p2_masked(grid: &Grid) -> usize {
+        let cubes = GridMask::from_grid(grid, '#');
+        let cycle = find_cycle(GridMask::from_grid(grid, 'O'), |rocks| spin_masked(rocks, &cubes));
 
-            storage.push(cycle_res);
+        return north_load(cycle.state_at(1000000000));
+    }
+
+    #[test]
+    fn sample_1_p2_masked() {
+        let grid = Grid::from_lines(SAMPLE_1);
+        let mut tilted = grid.clone();
+        lean_grid(&mut tilted, &NORTH);
+
+        assert_eq!(136, north_load(&GridMask::from_grid(&tilted, 'O')));
+        assert_eq!(64, apply_p2_masked(&grid));
+
+        // the masks spin exactly like the char grid
+        let cubes = GridMask::from_grid(&grid, '#');
+        let mut rocks = GridMask::from_grid(&grid, 'O');
+        let mut spun = grid.clone();
+        for _ in 0..5 {
+            rocks = spin_masked(&rocks, &cubes);
+            run_cycle(&mut spun);
+            assert_eq!(GridMask::from_grid(&spun, 'O'), rocks);
         }
+        assert_eq!(GridMask::from_grid(&tilted, 'O'), GridMask::from_grid(&grid, 'O').tilt(&cubes, &NORTH));
+    }
+
+    #[test]
+    fn sample_1_p2_brent() {
+        let grid = Grid::from_lines(SAMPLE_1);
 
-        return None;
+        let cycle = find_cycle(grid.print_string(), spin);
+        assert_eq!((cycle.offset, cycle.period), brent(&grid.print_string(), spin));
+
+        let x = brent_state_at(&grid.print_string(), spin, 1000000000);
+        assert_eq!(64, calculate_load(&Grid::from_lines(&x), &NORTH));
     }
 
     #[test]
     fn part_1() {
         let data = read_data_file(14, "input.txt");
 
-        let mut grid = Grid::from_lines(&data);
-        let results = apply_p1(&mut grid);
+        let results = apply_p1(&data);
         println!("Answer: {results:?}");
     }
 
     #[test]
     fn part_2() {
         let data = read_data_file(14, "input.txt");
-        let mut grid = Grid::from_lines(&data);
-
-
-        let results = apply_p2(&mut grid);
+        let results = apply_p2(&data);
         println!("Answer: {results:?}");
     }
 }
\ No newline at end of file
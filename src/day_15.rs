@@ -1,7 +1,5 @@
 #[cfg(test)]
 mod day15 {
-    use std::collections::VecDeque;
-
     use itertools::Itertools;
 
     use crate::read_data_file;
@@ -28,51 +26,126 @@ mod day15 {
             .sum()
     }
 
-    fn apply_p2(input: &str) -> usize {
-        let mut boxes: Vec<VecDeque<(&str, u8)>> = Vec::with_capacity(256);
+    #[derive(Debug, PartialEq)]
+    enum StepError {
+        MissingOperation { step: String },
+        EmptyLabel { step: String },
+        BadFocalLength { step: String },
+    }
 
-        for _ in 0..256 {
-            boxes.push(VecDeque::new());
+    #[derive(Debug, PartialEq)]
+    enum Step<'a> {
+        Remove(&'a str),
+        Insert(&'a str, u8),
+    }
+
+    fn parse_step(s: &str) -> Result<Step<'_>, StepError> {
+        let step = s.trim();
+        let (label, op) = if let Some(label) = step.strip_suffix('-') {
+            (label, Step::Remove(label))
+        } else if let Some((label, value)) = step.split_once('=') {
+            let focal = value
+                .parse::<u8>()
+                .ok()
+                .filter(|v| (1..=9).contains(v))
+                .ok_or(StepError::BadFocalLength { step: step.to_owned() })?;
+            (label, Step::Insert(label, focal))
+        } else {
+            return Err(StepError::MissingOperation { step: step.to_owned() });
+        };
+
+        if label.is_empty() {
+            return Err(StepError::EmptyLabel { step: step.to_owned() });
         }
 
-        input
-            .split(",")
-            .for_each(|s| {
-                if s.ends_with("-") {
-                    let label = &s[0 ..s.len()-1];
-                    let box_id = hash(label);
-                    let box_list = &mut boxes[box_id as usize];
-                    let pos = box_list.iter().find_position(|t| t.0 == label);
-                    if pos.is_some() {
-                        box_list.remove(pos.unwrap().0);
-                    }
-                } else {
-                    let (label, value_str) = s.split("=").collect_tuple().unwrap();
-                    let box_id = hash(label);
-                    let box_list = &mut boxes[box_id as usize];
-                    let value = value_str.parse::<u8>().unwrap();
-                    let pos = box_list.iter().find_position(|t| t.0 == label);
-
-                    if pos.is_some() {
-                        let idx = pos.unwrap().0;
-                        box_list[idx] = (label, value);
-                    } else {
-                        box_list.push_back((label, value));
+        return Ok(op);
+    }
+
+    /// The 256 boxes of the HASHMAP procedure. With tracing on, the puzzle-style box
+    /// listing is recorded after every step.
+    struct LensBoxes {
+        boxes: Vec<Vec<(String, u8)>>,
+        trace: Option<Vec<String>>,
+    }
+
+    impl LensBoxes {
+        fn new() -> LensBoxes {
+            return LensBoxes {
+                boxes: vec![vec![]; 256],
+                trace: None,
+            };
+        }
+
+        fn with_trace() -> LensBoxes {
+            return LensBoxes {
+                trace: Some(vec![]),
+                ..LensBoxes::new()
+            };
+        }
+
+        fn apply(&mut self, step: &str) -> Result<(), StepError> {
+            match parse_step(step)? {
+                Step::Remove(label) => {
+                    self.boxes[hash(label) as usize].retain(|(l, _)| l != label);
+                }
+                Step::Insert(label, focal) => {
+                    let box_list = &mut self.boxes[hash(label) as usize];
+                    match box_list.iter_mut().find(|(l, _)| l == label) {
+                        Some(lens) => lens.1 = focal,
+                        None => box_list.push((label.to_owned(), focal))
                     }
                 }
-            });
-
-        let mut result= 0;
-        for i in 0..256 {
-            let box_list = &boxes[i];
-            for j in 0..box_list.len() {
-                let (_, value) = box_list[j];
-                let score = (i + 1) * (j + 1) * value as usize;
-                result = result + score;
             }
+
+            let rendered = self.trace.as_ref().map(|_| self.render());
+            if let (Some(trace), Some(state)) = (self.trace.as_mut(), rendered) {
+                trace.push(format!("After \"{}\":\n{}", step.trim(), state));
+            }
+
+            return Ok(());
+        }
+
+        fn apply_all(&mut self, input: &str) -> Result<(), StepError> {
+            return input.split(',').try_for_each(|s| self.apply(s));
         }
 
-        return result;
+        /// Non-empty boxes with their lenses in slot order.
+        fn iter(&self) -> impl Iterator<Item=(usize, &Vec<(String, u8)>)> {
+            return self.boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| !b.is_empty());
+        }
+
+        fn box_power(&self, box_id: usize) -> usize {
+            return self.boxes[box_id]
+                .iter()
+                .enumerate()
+                .map(|(slot, (_, focal))| (box_id + 1) * (slot + 1) * *focal as usize)
+                .sum();
+        }
+
+        fn focusing_power(&self) -> usize {
+            return (0..self.boxes.len()).map(|i| self.box_power(i)).sum();
+        }
+
+        fn render(&self) -> String {
+            return self
+                .iter()
+                .map(|(i, b)| format!("Box {i}: {}", b.iter().map(|(l, f)| format!("[{l} {f}]")).join(" ")))
+                .join("\n");
+        }
+
+        fn trace(&self) -> Option<&Vec<String>> {
+            return self.trace.as_ref();
+        }
+    }
+
+    fn apply_p2(input: &str) -> usize {
+        let mut boxes = LensBoxes::new();
+        boxes.apply_all(input).unwrap();
+
+        return boxes.focusing_power();
     }
 
     #[test]
@@ -87,6 +160,7 @@ mod day15 {
 
 
         let results = apply_p1(data);
+        assert_eq!(1320, results);
         println!("Answer: {results:?}");
     }
 
@@ -95,9 +169,46 @@ mod day15 {
         let data = SAMPLE_1;
 
         let results = apply_p2(data);
+        assert_eq!(145, results);
         println!("Answer: {results:?}");
     }
 
+    #[test]
+    fn lens_boxes_contents() {
+        let mut boxes = LensBoxes::with_trace();
+        boxes.apply_all(SAMPLE_1).unwrap();
+
+        let contents = boxes
+            .iter()
+            .map(|(i, b)| (i, b.iter().map(|(l, f)| format!("{l}{f}")).collect_vec()))
+            .collect_vec();
+        assert_eq!(vec![(0, vec!["rn1".to_owned(), "cm2".to_owned()]), (3, vec!["ot7".to_owned(), "ab5".to_owned(), "pc6".to_owned()])], contents);
+        assert_eq!(5, boxes.box_power(0));
+        assert_eq!(140, boxes.box_power(3));
+
+        let trace = boxes.trace().unwrap();
+        assert_eq!(11, trace.len());
+        assert_eq!("After \"rn=1\":\nBox 0: [rn 1]", trace[0]);
+        assert_eq!("After \"qp=3\":\nBox 0: [rn 1]\nBox 1: [qp 3]", trace[2]);
+        assert_eq!("After \"qp-\":\nBox 0: [rn 1] [cm 2]", trace[4]);
+
+        assert!(LensBoxes::new().trace().is_none());
+    }
+
+    #[test]
+    fn lens_boxes_parse_errors() {
+        let mut boxes = LensBoxes::new();
+
+        assert_eq!(Err(StepError::MissingOperation { step: "rn".to_owned() }), boxes.apply("rn"));
+        assert_eq!(Err(StepError::EmptyLabel { step: "=4".to_owned() }), boxes.apply("=4"));
+        assert_eq!(Err(StepError::BadFocalLength { step: "rn=x".to_owned() }), boxes.apply("rn=x"));
+        assert_eq!(Err(StepError::BadFocalLength { step: "rn=12".to_owned() }), boxes.apply("rn=12"));
+        assert_eq!(0, boxes.iter().count());
+
+        assert_eq!(Ok(Step::Insert("rn", 1)), parse_step("rn=1"));
+        assert_eq!(Ok(Step::Remove("cm")), parse_step("cm-"));
+    }
+
     #[test]
     fn part_1() {
         let data = read_data_file(15, "input.txt");
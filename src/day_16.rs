@@ -2,8 +2,9 @@
 mod day16 {
     use std::collections::{HashSet, VecDeque};
 
-    use DIR::{EAST, NORTH, SOUTH};
+    use DIR::{EAST, NORTH};
 
+    use crate::beam::{BeamEngine, TileTable, UnknownTile};
     use crate::grid::{DIR, Grid, Point};
     use crate::grid::DIR::WEST;
     use crate::read_data_file;
@@ -19,65 +20,21 @@ mod day16 {
 .|....-|.\
 ..//.|...."#;
 
-    fn interact(c: char, dir: &DIR) -> Vec<DIR> {
-        match (c, dir.to_owned()) {
-            ('.', d) => vec![d],
-            ('/', NORTH) => vec![EAST],
-            ('/', EAST) => vec![NORTH],
-            ('/', SOUTH) => vec![WEST],
-            ('/', WEST) => vec![SOUTH],
-            ('\\', NORTH) => vec![WEST],
-            ('\\', EAST) => vec![SOUTH],
-            ('\\', SOUTH) => vec![EAST],
-            ('\\', WEST) => vec![NORTH],
-            ('|', NORTH) => vec![NORTH],
-            ('|', EAST) => vec![NORTH, SOUTH],
-            ('|', SOUTH) => vec![SOUTH],
-            ('|', WEST) => vec![NORTH, SOUTH],
-            ('-', NORTH) => vec![EAST, WEST],
-            ('-', EAST) => vec![EAST],
-            ('-', SOUTH) => vec![EAST, WEST],
-            ('-', WEST) => vec![WEST],
-            _ => { panic!("Invalid combo!") }
-        }
-    }
-
     fn apply_p1(input: &str) -> usize {
         let grid = Grid::from_lines(input);
+        let engine = BeamEngine::new(&grid, &TileTable::standard()).unwrap();
 
-        run_grid(&grid, Point::new(0, 0), EAST)
+        return engine.energized(Point::new(0, 0), EAST);
     }
 
     fn apply_p2(input: &str) -> usize {
         let grid = Grid::from_lines(input);
-        let (x_bounds, y_bounds) = grid.bounds();
-
-        let mut starts: Vec<(Point, DIR)> = vec![];
-
-        for x in x_bounds.to_owned() {
-            starts.push((Point::new(x as i32, 0), SOUTH));
-        }
-
-        for x in x_bounds.to_owned() {
-            starts.push((Point::new(x as i32, y_bounds.end as i32 - 1 ), NORTH));
-        }
-
-        for y in y_bounds.to_owned() {
-            starts.push((Point::new(0, y as i32), EAST));
-        }
+        let engine = BeamEngine::new(&grid, &TileTable::standard()).unwrap();
 
-        for y in y_bounds.to_owned() {
-            starts.push((Point::new(x_bounds.end as i32 - 1, y as i32 ), WEST));
-        }
-
-        starts
-            .into_iter()
-            .map(|(p, d)| run_grid(&grid, p, d))
-            .max()
-            .unwrap()
+        return engine.max_energized();
     }
 
-    fn run_grid(grid: &Grid, starting_point: Point, starting_dir: DIR) -> usize {
+    fn run_grid(grid: &Grid, table: &TileTable, starting_point: Point, starting_dir: DIR) -> usize {
         let mut grid_energy: HashSet<(Point, DIR)> = HashSet::new();
 
         let mut queue: VecDeque<(Point, DIR)> = VecDeque::new();
@@ -87,7 +44,7 @@ mod day16 {
             let (p, dir) = queue.pop_front().unwrap();
 
             let c = grid.get_point(&p).unwrap();
-            let new_dirs = interact(*c, &dir);
+            let new_dirs = table.outputs(*c, &dir).cloned().unwrap_or_default();
 
             for nd in new_dirs {
                 let maybe_np = grid.try_move(&p, &nd);
@@ -118,6 +75,7 @@ mod day16 {
 
 
         let results = apply_p1(data);
+        assert_eq!(46, results);
         println!("Answer: {results:?}");
     }
 
@@ -126,9 +84,42 @@ mod day16 {
         let data = SAMPLE_1;
 
         let results = apply_p2(data);
+        assert_eq!(51, results);
         println!("Answer: {results:?}");
     }
 
+    #[test]
+    fn engine_matches_simulation() {
+        let grid = Grid::from_lines(SAMPLE_1);
+        let table = TileTable::standard();
+        let engine = BeamEngine::new(&grid, &table).unwrap();
+
+        assert_eq!(40, engine.edge_entries().len());
+        for (p, d) in engine.edge_entries() {
+            assert_eq!(run_grid(&grid, &table, p, d), engine.energized(p, d));
+        }
+
+        // entries away from the edge are walked on demand
+        assert_eq!(run_grid(&grid, &table, Point::new(4, 4), WEST), engine.energized(Point::new(4, 4), WEST));
+        assert_eq!(run_grid(&grid, &table, Point::new(6, 6), NORTH), engine.energized(Point::new(6, 6), NORTH));
+    }
+
+    #[test]
+    fn engine_tile_table() {
+        let grid = Grid::from_lines("..#..\n.x...");
+        assert_eq!(Err(UnknownTile { tile: '#', at: Point::new(2, 0) }), BeamEngine::new(&grid, &TileTable::standard()).map(|_| ()));
+
+        let table = TileTable::standard()
+            .with_tile('#', |_| vec![])
+            .with_tile('x', |d| vec![d]);
+        let engine = BeamEngine::new(&grid, &table).unwrap();
+        assert_eq!(3, engine.energized(Point::new(0, 0), EAST));
+        assert_eq!(5, engine.energized(Point::new(0, 1), EAST));
+
+        let cells = engine.energized_cells(Point::new(4, 0), WEST);
+        assert_eq!(vec![2, 3, 4], (0..10).filter(|i| cells.get(*i)).collect::<Vec<_>>());
+    }
+
     #[test]
     fn part_1() {
         let data = read_data_file(16, "input.txt");
@@ -2,11 +2,14 @@
 mod day17 {
     use std::cmp;
 
+    use itertools::Itertools;
+
     use DIR::SOUTH;
 
     use crate::{read_data_file, shortest_path};
     use crate::grid::{DIR, Grid, Point};
     use crate::grid::DIR::{EAST, NORTH, WEST};
+    use crate::momentum::{CostGrid, find_route, MovementPolicy, Route, Turn};
 
     static SAMPLE_1: &str = r#"2413432311323
 3215453535623
@@ -68,12 +71,19 @@ mod day17 {
         );
     }
 
-    fn apply_p1(input: &str) -> i64 {
-        return run_grid(input, 1, 3);
+    fn run_policy(input: &str, policy: &MovementPolicy) -> Option<Route> {
+        let grid = CostGrid::from_digits(&Grid::from_lines(input));
+        let goal = Point::new(grid.x_size as i32 - 1, grid.y_size as i32 - 1);
+
+        return find_route(&grid, policy, Point::new(0, 0), goal);
+    }
+
+    fn apply_p1(input: &str) -> u64 {
+        return run_policy(input, &MovementPolicy::crucible()).unwrap().cost;
     }
 
-    fn apply_p2(input: &str) -> i64 {
-        return run_grid(input, 4, 10);
+    fn apply_p2(input: &str) -> u64 {
+        return run_policy(input, &MovementPolicy::ultra_crucible()).unwrap().cost;
     }
 
     #[test]
@@ -82,6 +92,7 @@ mod day17 {
 
 
         let results = apply_p1(data);
+        assert_eq!(102, results);
         println!("Answer: {results:?}");
     }
 
@@ -90,9 +101,79 @@ mod day17 {
         let data = SAMPLE_1;
 
         let results = apply_p2(data);
+        assert_eq!(94, results);
         println!("Answer: {results:?}");
     }
 
+    #[test]
+    fn sample_2_p2() {
+        let data = "111111111111\n999999999991\n999999999991\n999999999991\n999999999991";
+
+        assert_eq!(71, apply_p2(data));
+    }
+
+    #[test]
+    fn route_path() {
+        let grid = Grid::from_lines(SAMPLE_1);
+        let route = run_policy(SAMPLE_1, &MovementPolicy::crucible()).unwrap();
+
+        assert_eq!(Point::new(0, 0), route.path[0]);
+        assert_eq!(Point::new(12, 12), *route.path.last().unwrap());
+        assert_eq!(
+            route.cost,
+            route.path[1..].iter().map(|p| grid.get_point(p).unwrap().to_digit(10).unwrap() as u64).sum::<u64>()
+        );
+        assert!(route.path.iter().tuple_windows().all(|(a, b)| a.x.abs_diff(b.x) + a.y.abs_diff(b.y) == 1));
+    }
+
+    #[test]
+    fn route_zero_cost_cells() {
+        // every path costs nothing, so distances alone can't tell the way back
+        let grid = CostGrid::from_digits(&Grid::from_lines("000\n000"));
+        let reverse = MovementPolicy { min_run: 1, max_run: 3, turns: vec![Turn::Left, Turn::Right, Turn::Reverse] };
+
+        for policy in [reverse, MovementPolicy::crucible()] {
+            let route = find_route(&grid, &policy, Point::new(0, 0), Point::new(2, 1)).unwrap();
+
+            assert_eq!(0, route.cost);
+            assert_eq!(Point::new(0, 0), route.path[0]);
+            assert_eq!(Point::new(2, 1), *route.path.last().unwrap());
+            assert!(route.path.iter().tuple_windows().all(|(a, b)| a.x.abs_diff(b.x) + a.y.abs_diff(b.y) == 1));
+        }
+    }
+
+    #[test]
+    fn route_policies() {
+        let data = "1999\n1999\n1111";
+        let free = MovementPolicy { min_run: 1, max_run: 10, turns: vec![Turn::Left, Turn::Right, Turn::Reverse] };
+        assert_eq!(5, run_policy(data, &free).unwrap().cost);
+
+        // going south then east is a left turn, so a right-only mover has to go round
+        let right_only = MovementPolicy { turns: vec![Turn::Right], ..free.clone() };
+        assert_eq!(37, run_policy(data, &right_only).unwrap().cost);
+
+        // a mover that can only reverse makes no progress unless its run reaches the goal
+        let bounce = MovementPolicy { min_run: 1, max_run: 1, turns: vec![Turn::Reverse] };
+        assert_eq!(None, run_policy("1111", &bounce));
+        assert_eq!(Some(3), run_policy("1111", &MovementPolicy { max_run: 3, ..bounce }).map(|r| r.cost));
+
+        let walled = CostGrid::from_grid(&Grid::from_lines("1#1\n1#1\n111"), |c| c.to_digit(10));
+        let route = find_route(&walled, &free, Point::new(0, 0), Point::new(2, 0)).unwrap();
+        assert_eq!(6, route.cost);
+        assert_eq!(7, route.path.len());
+    }
+
+    #[test]
+    fn sample_1_dial_matches_heap() {
+        let data = SAMPLE_1;
+
+        // `find_route` runs on Dial's buckets, `run_grid` on the binary heap
+        assert_eq!(102, run_grid(data, 1, 3));
+        assert_eq!(102, apply_p1(data));
+        assert_eq!(94, run_grid(data, 4, 10));
+        assert_eq!(94, apply_p2(data));
+    }
+
     #[test]
     fn part_1() {
         let data = read_data_file(17, "input.txt");
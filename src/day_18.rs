@@ -1,11 +1,11 @@
 #[cfg(test)]
 mod day18 {
     use itertools::Itertools;
-    use num::abs;
     use regex::Regex;
 
-    use crate::grid::DIR;
+    use crate::grid::{DIR, Grid, Point};
     use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+    use crate::polygon::{Orientation, Polygon};
     use crate::read_data_file;
 
     static SAMPLE_1: &str = r#"R 6 (#70c710)
@@ -23,42 +23,55 @@ U 3 (#a77fa3)
 L 2 (#015232)
 U 2 (#7a21e3)"#;
 
-    struct Instruction {
-        dir: DIR,
-        dist: i64,
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum Encoding {
+        // direction letter and distance
+        Plain,
+        // distance and direction packed into the colour hex
+        Hex,
     }
 
-    fn parse_instruction_p1(input: &str) -> Instruction {
-        let reg = Regex::new(r"([UDLR]) (\d*)").unwrap();
-        let caps = reg.captures(input).unwrap();
+    #[derive(Debug, PartialEq, Eq)]
+    enum DigPlanError {
+        Malformed { line: usize, text: String },
+        NotClosed { end: (i64, i64) },
+        SelfCrossing,
+    }
 
-        return Instruction {
-            dir: match caps[1].to_owned().as_str() {
-                "U" => NORTH,
-                "D" => SOUTH,
-                "L" => EAST,
-                "R" => WEST,
-                _ => panic!("Can't happen")
-            },
-            dist: caps[2].parse::<i64>().unwrap(),
-        };
+    struct Instruction {
+        dir: DIR,
+        dist: i64,
+        colour: String,
     }
 
-    fn parse_instruction_p2(input: &str) -> Instruction {
-        let reg = Regex::new(r"([UDLR]) (\d*) \(#(.*)\)").unwrap();
-        let caps = reg.captures(input).unwrap();
+    fn parse_instruction(input: &str, encoding: Encoding) -> Option<Instruction> {
+        let reg = Regex::new(r"^([UDLR]) (\d+) \(#([0-9a-f]{6})\)$").unwrap();
+        let caps = reg.captures(input)?;
 
-        let hex = &caps[3];
+        let colour = caps[3].to_owned();
 
-        return Instruction {
-            dir: match &hex[5..] {
-                "3" => NORTH,
-                "1" => SOUTH,
-                "2" => EAST,
-                "0" => WEST,
-                _ => panic!("Can't happen")
-            },
-            dist: i64::from_str_radix(&hex[0..5], 16).unwrap()
+        return match encoding {
+            Encoding::Plain => Some(Instruction {
+                dir: match &caps[1] {
+                    "U" => NORTH,
+                    "D" => SOUTH,
+                    "L" => WEST,
+                    _ => EAST
+                },
+                dist: caps[2].parse::<i64>().ok()?,
+                colour,
+            }),
+            Encoding::Hex => Some(Instruction {
+                dir: match &colour[5..] {
+                    "3" => NORTH,
+                    "1" => SOUTH,
+                    "2" => WEST,
+                    "0" => EAST,
+                    _ => return None
+                },
+                dist: i64::from_str_radix(&colour[0..5], 16).ok()?,
+                colour,
+            })
         };
     }
 
@@ -71,53 +84,101 @@ U 2 (#7a21e3)"#;
         }
     }
 
-    fn apply_p1(input: &str) -> i64 {
-        let instructions = input.lines().map(|l| parse_instruction_p1(l)).collect_vec();
+    struct Segment {
+        start: (i64, i64),
+        end: (i64, i64),
+        colour: String,
+    }
 
-        return calculate_area(instructions);
+    struct DigPlan {
+        segments: Vec<Segment>,
     }
 
-    fn apply_p2(input: &str) -> i64 {
-        let instructions = input.lines().map(|l| parse_instruction_p2(l)).collect_vec();
+    impl DigPlan {
+        fn parse(input: &str, encoding: Encoding) -> Result<DigPlan, DigPlanError> {
+            let instructions = input
+                .lines()
+                .enumerate()
+                .map(|(i, l)| parse_instruction(l, encoding).ok_or(DigPlanError::Malformed { line: i + 1, text: l.to_owned() }))
+                .collect::<Result<Vec<_>, _>>()?;
 
-        return calculate_area(instructions);
-    }
+            return DigPlan::trace(instructions);
+        }
+
+        fn trace(instructions: Vec<Instruction>) -> Result<DigPlan, DigPlanError> {
+            let mut start = (0, 0);
+            let mut segments = vec![];
+
+            for inst in instructions {
+                let end = mv(start, inst.dist, inst.dir);
+                segments.push(Segment { start, end, colour: inst.colour });
+                start = end;
+            }
 
-    fn calculate_area(instructions: Vec<Instruction>) -> i64 {
-        let mut start = (0, 0);
-        let mut data = vec![start.to_owned()];
+            if start != (0, 0) {
+                return Err(DigPlanError::NotClosed { end: start });
+            }
 
-        for inst in instructions {
-            let end = mv(start, inst.dist, inst.dir);
-            data.push(end);
-            start = end;
+            let plan = DigPlan { segments };
+            if plan.polygon().is_self_intersecting() {
+                return Err(DigPlanError::SelfCrossing);
+            }
+
+            return Ok(plan);
         }
 
-        let shoelace_area = calculate_shoelace_area(&data);
-        let perimeter = calculate_perimeter(&data);
-        return shoelace_area + perimeter / 2 + 1;
-    }
+        fn polygon(&self) -> Polygon {
+            return Polygon::new(self.segments.iter().map(|s| s.start).collect_vec());
+        }
 
-    fn calculate_perimeter(data: &Vec<(i64, i64)>) -> i64 {
-        data
-            .iter()
-            .tuple_windows()
-            .map(|((x1, y1), (x2, y2))| {
-                abs(y2-y1) + abs (x2-x1)
-            })
-            .sum()
+        fn area(&self) -> i64 {
+            return self.polygon().lattice_points();
+        }
+
+        fn bounds(&self) -> ((i64, i64), (i64, i64)) {
+            let (min_x, max_x) = self.segments.iter().map(|s| s.start.0).minmax().into_option().unwrap();
+            let (min_y, max_y) = self.segments.iter().map(|s| s.start.1).minmax().into_option().unwrap();
+
+            return ((min_x, min_y), (max_x, max_y));
+        }
+
+        // only sensible for part 1 sized plans, every metre becomes a cell
+        fn rasterize(&self, filled: bool) -> Grid {
+            let ((min_x, min_y), (max_x, max_y)) = self.bounds();
+            let mut grid = Grid::from_size((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+            let to_point = |(x, y): (i64, i64)| Point::new((x - min_x) as i32, (y - min_y) as i32);
+
+            if filled {
+                let polygon = self.polygon();
+                for y in min_y..=max_y {
+                    for x in min_x..=max_x {
+                        if polygon.contains((x, y)) {
+                            grid.set_point(&to_point((x, y)), '#');
+                        }
+                    }
+                }
+            }
+
+            for s in &self.segments {
+                let (dx, dy) = ((s.end.0 - s.start.0).signum(), (s.end.1 - s.start.1).signum());
+                let mut current = s.start;
+                grid.set_point(&to_point(current), '#');
+                while current != s.end {
+                    current = (current.0 + dx, current.1 + dy);
+                    grid.set_point(&to_point(current), '#');
+                }
+            }
+
+            return grid;
+        }
     }
 
-    fn calculate_shoelace_area(data: &Vec<(i64, i64)>) -> i64 {
-        let two_a: i64 = data
-            .iter()
-            .tuple_windows()
-            .map(|((x1, y1), (x2, y2))| {
-                (x1 * y2) - (y1 * x2)
-            })
-            .sum();
+    fn apply_p1(input: &str) -> i64 {
+        return DigPlan::parse(input, Encoding::Plain).unwrap().area();
+    }
 
-        abs(two_a / 2)
+    fn apply_p2(input: &str) -> i64 {
+        return DigPlan::parse(input, Encoding::Hex).unwrap().area();
     }
 
     #[test]
@@ -126,6 +187,7 @@ U 2 (#7a21e3)"#;
 
 
         let results = apply_p1(data);
+        assert_eq!(62, results);
         println!("Answer: {results:?}");
     }
 
@@ -134,9 +196,62 @@ U 2 (#7a21e3)"#;
         let data = SAMPLE_1;
 
         let results = apply_p2(data);
+        assert_eq!(952408144115, results);
         println!("Answer: {results:?}");
     }
 
+    #[test]
+    fn sample_1_trace() {
+        let plan = DigPlan::parse(SAMPLE_1, Encoding::Plain).unwrap();
+
+        assert_eq!("70c710", plan.segments[0].colour);
+        assert_eq!((6, 0), plan.segments[0].end);
+        assert_eq!(((0, 0), (6, 9)), plan.bounds());
+
+        let trench = plan.rasterize(false);
+        assert_eq!(38, trench.print_string().chars().filter(|c| *c == '#').count());
+        assert_eq!("#######", &trench.print_string()[0..7]);
+
+        let lagoon = plan.rasterize(true);
+        assert_eq!(62, lagoon.print_string().chars().filter(|c| *c == '#').count());
+    }
+
+    #[test]
+    fn dig_plan_errors() {
+        assert_eq!(
+            Some(DigPlanError::Malformed { line: 2, text: "X 5 (#0dc571)".to_owned() }),
+            DigPlan::parse("R 6 (#70c710)\nX 5 (#0dc571)", Encoding::Plain).err()
+        );
+
+        assert_eq!(
+            Some(DigPlanError::NotClosed { end: (6, 5) }),
+            DigPlan::parse("R 6 (#70c710)\nD 5 (#0dc571)", Encoding::Plain).err()
+        );
+
+        let figure_eight = "R 2 (#000000)\nD 2 (#000000)\nR 2 (#000000)\nU 4 (#000000)\nL 2 (#000000)\nD 2 (#000000)\nL 2 (#000000)";
+        assert_eq!(Some(DigPlanError::SelfCrossing), DigPlan::parse(figure_eight, Encoding::Plain).err());
+    }
+
+    #[test]
+    fn polygon_queries() {
+        let square = Polygon::new(vec![(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(16, square.area());
+        assert_eq!(16, square.perimeter());
+        assert_eq!(16, square.boundary_points());
+        assert_eq!(9, square.interior_points());
+        assert_eq!(Orientation::CounterClockwise, square.orientation());
+        assert!(square.contains((2, 2)));
+        assert!(!square.contains((4, 2)) && square.on_boundary((4, 2)));
+        assert!(!square.contains((5, 2)));
+        assert!(!square.is_self_intersecting());
+
+        let bow_tie = Polygon::new(vec![(0, 0), (4, 4), (4, 0), (0, 4)]);
+        assert!(bow_tie.is_self_intersecting());
+
+        let doubled_back = Polygon::new(vec![(0, 0), (4, 0), (2, 0), (2, 2)]);
+        assert!(doubled_back.is_self_intersecting());
+    }
+
     #[test]
     fn part_1() {
         let data = read_data_file(18, "input.txt");
@@ -1,12 +1,12 @@
 #[cfg(test)]
 mod day19 {
-    use std::cmp;
-    use std::collections::HashMap;
-
     use itertools::Itertools;
-    use regex::Regex;
 
+    use crate::decision_table::{decision_table, simplify, Step, to_csv};
+    use crate::hyperrect::Hyperrect;
     use crate::read_data_file;
+    use crate::workflow_analysis::{analyze, Issue, live_rules};
+    use crate::workflow::{Attribute, CompiledWorkflows, CompileError, EvalError, parse_system, ParseError, Target, Verdict, WorkflowSet};
 
     static SAMPLE_1: &str = r#"px{a<2006:qkq,m>2090:A,rfg}
 pv{a>1716:R,A}
@@ -26,282 +26,275 @@ hdj{m>838:A,pv}
 {x=2461,m=1339,a=466,s=291}
 {x=2127,m=1623,a=2188,s=1013}"#;
 
-    struct Rule {
-        att: Option<String>,
-        op: Option<String>,
-        value: Option<i64>,
-        dest: String,
+    fn xmas() -> Hyperrect {
+        return Hyperrect::uniform(&Attribute::XMAS, (1, 4000));
     }
 
-    impl Rule {
-        fn matches(&self, p: &Part) -> bool {
-            if self.att.is_none() {
-                return true;
-            }
-
-            let att = self.att.as_ref().unwrap().as_str();
-            let op = self.op.as_ref().unwrap().as_str();
-            let value = self.value.unwrap();
-
-            match op {
-                "<" => {
-                    match att {
-                        "x" => p.x < value,
-                        "m" => p.m < value,
-                        "a" => p.a < value,
-                        "s" => p.s < value,
-                        _ => panic!("Cant get here")
-                    }
-                }
-                ">" => {
-                    match att {
-                        "x" => p.x > value,
-                        "m" => p.m > value,
-                        "a" => p.a > value,
-                        "s" => p.s > value,
-                        _ => panic!("Cant get here")
-                    }
-                }
-                _ => panic!("can't get here either")
-            }
-        }
+    fn count_accepted(workflows: &WorkflowSet, domain: &Hyperrect) -> Result<u128, Vec<Issue>> {
+        return Ok(decision_table(workflows, "in", domain)?
+            .iter()
+            .filter(|d| d.verdict == Verdict::Accept)
+            .map(|d| d.region.volume())
+            .sum());
     }
 
-    struct Part {
-        x: i64,
-        m: i64,
-        a: i64,
-        s: i64,
-    }
+    fn apply_p2(input: &str) -> u128 {
+        let (workflows, _) = parse_system(input).unwrap();
 
-    #[derive(Clone)]
-    struct PartRange {
-        x_min: i64,
-        x_max: i64,
-        m_min: i64,
-        m_max: i64,
-        a_min: i64,
-        a_max: i64,
-        s_min: i64,
-        s_max: i64,
+        return count_accepted(&workflows, &xmas()).unwrap();
     }
 
+    fn apply_p1(input: &str) -> i64 {
+        let (workflows, parts) = parse_system(input).unwrap();
+        let compiled = CompiledWorkflows::compile(&workflows, "in").unwrap();
 
-    impl PartRange {
-        fn new() -> PartRange {
-            return PartRange {
-                x_min: 1,
-                x_max: 4000,
-                m_min: 1,
-                m_max: 4000,
-                a_min: 1,
-                a_max: 4000,
-                s_min: 1,
-                s_max: 4000,
-            };
-        }
+        return parts
+            .iter()
+            .filter(|p| compiled.evaluate(p) == Ok(Verdict::Accept))
+            .map(|p| p.total())
+            .sum();
     }
 
-    fn parse_data(input: &str) -> (HashMap<String, Vec<Rule>>, Vec<Part>) {
-        let (rules_str, parts_str) = input.split("\n\n").collect_tuple().unwrap();
+    #[test]
+    fn sample_1_p1() {
+        let data = SAMPLE_1;
 
-        let rules = parse_workflows(rules_str);
-        let parts = parse_parts(parts_str);
 
-        return (rules, parts);
+        let results = apply_p1(data);
+        assert_eq!(19114, results);
+        println!("Answer: {results:?}");
     }
 
-    fn parse_parts(input: &str) -> Vec<Part> {
-        let regx = Regex::new(r"\{x=(\d*),m=(\d*),a=(\d*),s=(\d*)}").unwrap();
-        input
-            .lines()
-            .map(|l| regx.captures(l).unwrap())
-            .map(|c| Part {
-                x: (&c[1]).parse::<i64>().unwrap(),
-                m: (&c[2]).parse::<i64>().unwrap(),
-                a: (&c[3]).parse::<i64>().unwrap(),
-                s: (&c[4]).parse::<i64>().unwrap(),
-            })
-            .collect_vec()
-    }
+    #[test]
+    fn sample_1_p2() {
+        let data = SAMPLE_1;
 
-    fn parse_workflows(input: &str) -> HashMap<String, Vec<Rule>> {
-        let name_regex = Regex::new(r"(.*)\{(.*)}").unwrap();
-        return input
-            .lines()
-            .map(|l| name_regex.captures(l).unwrap())
-            .map(|c| {
-                let name = c[1].to_owned();
-                let rule_str = &c[2];
-                let rules = parse_rules(rule_str);
-
-                return (name, rules);
-            })
-            .collect::<HashMap<_, _>>();
+        let results = apply_p2(data);
+        assert_eq!(167409079868000, results);
+        println!("Answer: {results:?}");
     }
 
-    fn parse_rules(input: &str) -> Vec<Rule> {
-        input
-            .split(",")
-            .map(|s| parse_rule(s))
-            .collect_vec()
+    #[test]
+    fn parse_errors() {
+        let err = |input: &str| parse_system(input).err().map(|e| (e.line, e.column));
+
+        assert_eq!(Some((1, 7)), err("in{a<5A,R}"));
+        assert_eq!(Some((2, 6)), err("in{A}\nqq{x>:A,R}"));
+        assert_eq!(Some((1, 4)), err("in{4<x:A,R}"));
+        assert_eq!(Some((1, 8)), err("in{A,R}}"));
+        assert_eq!(Some((3, 5)), err("in{A}\n\n{x=1;m=2}"));
+        assert_eq!(Some((2, 1)), err("in{A}\nin{R}"));
+
+        let e = parse_system("in{x<1:A,R\n").unwrap_err();
+        assert_eq!(ParseError { line: 1, column: 11, message: "expected ',' or '}'".to_owned() }, e);
     }
 
-    fn parse_rule(input: &str) -> Rule {
-        if !input.contains(':') {
-            return Rule {
-                att: None,
-                op: None,
-                value: None,
-                dest: input.to_owned(),
-            };
-        }
-
-        let regex = Regex::new(r"(.*)([<>])(\d*):(.*)").unwrap();
-        let caps = regex.captures(input).unwrap();
-
-        return Rule {
-            att: Some(caps[1].to_owned()),
-            op: Some(caps[2].to_owned()),
-            value: Some(caps[3].parse::<i64>().unwrap()),
-            dest: caps[4].to_owned(),
-        };
+    #[test]
+    fn extended_comparisons() {
+        let input = "in{x<=10:A,m>=20:lo,a==5:A,R}\nlo{s==1:R,A}\n\n{x=10,m=0,a=0,s=0}\n{x=11,m=20,a=0,s=1}\n{x=11,m=20,a=0,s=2}\n{x=11,m=0,a=5,s=0}\n{x=11,m=0,a=6,s=0}";
+        let (workflows, parts) = parse_system(input).unwrap();
+        let compiled = CompiledWorkflows::compile(&workflows, "in").unwrap();
+
+        let verdicts = parts.iter().map(|p| compiled.evaluate(p).unwrap()).collect::<Vec<_>>();
+        assert_eq!(vec![Verdict::Accept, Verdict::Reject, Verdict::Accept, Verdict::Accept, Verdict::Reject], verdicts);
+
+        // x<=10: 10*4000^3, then x>10 & m>=20 & s!=1: 3990*3981*4000*3999,
+        // then x>10 & m<20 & a==5: 3990*19*1*4000
+        let expected = 10 * 4000u128.pow(3) + 3990 * 3981 * 4000 * 3999 + 3990 * 19 * 4000;
+        assert_eq!(expected, apply_p2(input));
     }
 
-    fn apply_p2(input: &str) -> i64 {
-        let (workflows, _) = parse_data(input);
+    #[test]
+    fn compile_and_eval_errors() {
+        let (workflows, parts) = parse_system("in{x<5:A,qq}\n\n{m=3}").unwrap();
+        assert_eq!(Err(CompileError::UndefinedWorkflow { name: "qq".to_owned(), used_in: "in".to_owned() }), CompiledWorkflows::compile(&workflows, "in").map(|_| ()));
+        assert_eq!(Err(CompileError::MissingStart { name: "start".to_owned() }), CompiledWorkflows::compile(&workflows, "start").map(|_| ()));
 
-        let (approved, _) = walk_tree(PartRange::new(), workflows.get("in").unwrap(), &workflows);
+        let (workflows, _) = parse_system("in{x<5:A,qq}\nqq{m>3:R,in}").unwrap();
+        let compiled = CompiledWorkflows::compile(&workflows, "in").unwrap();
+        assert_eq!(Err(EvalError::MissingRating { attribute: Attribute::X }), compiled.evaluate(&parts[0]));
 
-        approved
-            .iter()
-            .map(|pr| {
-                (pr.x_max - pr.x_min + 1) * (pr.m_max - pr.m_min + 1) * (pr.a_max - pr.a_min + 1) * (pr.s_max - pr.s_min + 1)
-            })
-            .sum()
-    }
+        let (_, looping) = parse_system("{x=9,m=1}").unwrap();
+        assert!(matches!(compiled.evaluate(&looping[0]), Err(EvalError::Cycle { .. })));
 
-    fn apply_op_to_range(op: &str, val: i64, rng: (i64, i64)) -> (i64, i64) {
-        match op {
-            ">" => (cmp::max(rng.0, val + 1), rng.1),
-            "<" => (rng.0, cmp::min(rng.1, val - 1)),
-            ">=" => (cmp::max(rng.0, val), rng.1),
-            "<=" => (rng.0, cmp::min(rng.1, val)),
-            _ => panic!("Err")
-        }
+        let (workflows, _) = parse_system("in{x<5:A}").unwrap();
+        let compiled = CompiledWorkflows::compile(&workflows, "in").unwrap();
+        assert_eq!(Err(EvalError::NoRuleMatched { workflow: "in".to_owned() }), compiled.evaluate(&looping[0]));
     }
 
-    fn apply_rule_to_part_range(att: &str, op: &str, value: i64, part_range: &PartRange) -> PartRange {
-        let mut new_range = part_range.clone();
-        match att {
-            "x" => {
-                (new_range.x_min, new_range.x_max) = apply_op_to_range(op, value, (new_range.x_min, new_range.x_max))
-            }
-            "m" => {
-                (new_range.m_min, new_range.m_max) = apply_op_to_range(op, value, (new_range.m_min, new_range.m_max))
-            }
-            "a" => {
-                (new_range.a_min, new_range.a_max) = apply_op_to_range(op, value, (new_range.a_min, new_range.a_max))
-            }
-            "s" => {
-                (new_range.s_min, new_range.s_max) = apply_op_to_range(op, value, (new_range.s_min, new_range.s_max))
-            }
-            _ => panic!("Err")
-        }
-
-        return new_range;
+    #[test]
+    fn analyze_sample() {
+        let (workflows, _) = parse_system(SAMPLE_1).unwrap();
+
+        assert_eq!(
+            vec![
+                Issue::AlwaysAccepts { workflow: "lnx".to_owned() },
+                Issue::AlwaysAccepts { workflow: "qs".to_owned() },
+                Issue::AlwaysRejects { workflow: "gd".to_owned() },
+            ],
+            analyze(&workflows, "in", &xmas())
+        );
+        assert_eq!(vec![Issue::MissingStart { name: "start".to_owned() }], analyze(&workflows, "start", &xmas()).into_iter().take(1).collect_vec());
     }
 
-    fn walk_tree(pr: PartRange, wf: &Vec<Rule>, workflows: &HashMap<String, Vec<Rule>>) -> (Vec<PartRange>, Vec<PartRange>) {
-        let mut approved = vec![];
-        let mut rejected = vec![];
-
-        let mut remaining_range = pr.clone();
-        for r in wf {
-            let mut passed_range = remaining_range.clone();
+    #[test]
+    fn analyze_problems() {
+        let input = "in{x<10:a,x<5:R,b}\na{m>0:A,zz}\nb{s>1:in,R}\nc{A}";
+        let (workflows, _) = parse_system(input).unwrap();
+
+        assert_eq!(vec![true, false, true], live_rules(workflows.get("in").unwrap(), &xmas()));
+        assert_eq!(vec![true, true], live_rules(workflows.get("a").unwrap(), &Hyperrect::uniform(&Attribute::XMAS, (0, 4000))));
+
+        let issues = analyze(&workflows, "in", &xmas());
+        assert_eq!(
+            vec![
+                Issue::UndefinedTarget { workflow: "a".to_owned(), rule: 1, target: "zz".to_owned() },
+                Issue::GraphCycle { workflows: vec!["in".to_owned(), "b".to_owned()] },
+                Issue::Cycle { workflows: vec!["in".to_owned(), "b".to_owned()] },
+                Issue::Unreachable { workflow: "c".to_owned() },
+                Issue::DeadRule { workflow: "in".to_owned(), rule: 1 },
+                Issue::DeadRule { workflow: "a".to_owned(), rule: 1 },
+                Issue::AlwaysAccepts { workflow: "a".to_owned() },
+                Issue::AlwaysAccepts { workflow: "c".to_owned() },
+            ],
+            issues
+        );
+
+        let fatal = count_accepted(&workflows, &xmas()).unwrap_err();
+        assert_eq!(2, fatal.len());
+
+        // `b` only sees parts with x<10 and only sends x>20 back, so nothing loops
+        let (workflows, _) = parse_system("in{x<10:b,A}\nb{x>20:in,R}").unwrap();
+        let issues = analyze(&workflows, "in", &xmas());
+        assert!(issues.contains(&Issue::GraphCycle { workflows: vec!["in".to_owned(), "b".to_owned()] }));
+        assert!(!issues.iter().any(|i| i.is_fatal()));
+        assert_eq!(Ok(3991 * 4000u128.pow(3)), count_accepted(&workflows, &xmas()));
+
+        // with `zz` only reachable through a live rule its outcome is unknown
+        let (workflows, _) = parse_system("in{m>5:A,zz}").unwrap();
+        assert_eq!(
+            vec![Issue::UndefinedTarget { workflow: "in".to_owned(), rule: 1, target: "zz".to_owned() }],
+            analyze(&workflows, "in", &xmas())
+        );
+    }
 
-            if r.att.is_some() {
-                let att = r.att.as_ref().unwrap().as_str();
-                let op = r.op.as_ref().unwrap().as_str();
-                let value = r.value.unwrap();
+    #[test]
+    fn hyperrect_algebra() {
+        let x = Attribute::X;
+        let m = Attribute::M;
+        let square = Hyperrect::uniform(&[x.clone(), m.clone()], (1, 10));
+        assert_eq!(100, square.volume());
+
+        let (below, above) = square.split_at(&x, 4);
+        assert_eq!((30, 70), (below.unwrap().volume(), above.unwrap().volume()));
+        assert_eq!(None, square.split_at(&x, 1).0);
+
+        let inner = Hyperrect::uniform(&[x.clone(), m.clone()], (4, 6));
+        assert_eq!(Some(inner.clone()), square.intersection(&inner));
+        assert_eq!(None, inner.intersection(&Hyperrect::new([(x.clone(), (7, 9))])));
+
+        let pieces = square.subtract(&inner);
+        assert_eq!(91, pieces.iter().map(|p| p.volume()).sum::<u128>());
+        assert!(pieces.iter().tuple_combinations().all(|(a, b)| a.intersection(b).is_none()));
+        assert!(pieces.iter().all(|p| p.intersection(&inner).is_none()));
+        assert_eq!(vec![square.clone()], square.subtract(&Hyperrect::new([(m.clone(), (20, 30))])));
+
+        let (_, parts) = parse_system("{x=5,m=10}\n{x=5,m=11}\n{x=5}").unwrap();
+        assert_eq!(vec![true, false, false], parts.iter().map(|p| square.contains(&p.ratings)).collect_vec());
+
+        // a box without `m` is unbounded along it
+        let strip = Hyperrect::new([(x.clone(), (1, 2))]);
+        assert_eq!(Some(Hyperrect::uniform(&[x, m], (1, 2)).with(&Attribute::M, (1, 10))), strip.intersection(&square));
+    }
 
-                let opposite_op = if op == "<" { ">=" } else { "<=" };
-                passed_range = apply_rule_to_part_range(att, op, value, &remaining_range);
-                remaining_range = apply_rule_to_part_range(att, opposite_op, value, &remaining_range);
-            }
+    #[test]
+    fn arbitrary_attributes() {
+        let input = "in{speed>=50:fast,weight<10:A,R}\nfast{weight==0:R,A}";
+        let (workflows, _) = parse_system(input).unwrap();
+        let speed = Attribute::from_name("speed");
+        let weight = Attribute::from_name("weight");
+
+        assert_eq!(Attribute::Named("speed".to_owned()), speed);
+        assert_eq!("weight", weight.name());
+
+        // speed 0..=99, weight 0..=19: fast accepts 50 * 19, slow accepts 50 * 10
+        let domain = Hyperrect::new([(speed, (0, 99)), (weight, (0, 19))]);
+        assert_eq!(Ok(50 * 19 + 50 * 10), count_accepted(&workflows, &domain));
+    }
 
+    #[test]
+    fn sample_decision_table() {
+        let (workflows, parts) = parse_system(SAMPLE_1).unwrap();
+        let table = decision_table(&workflows, "in", &xmas()).unwrap();
 
-            if r.dest == "A" {
-                approved.push(passed_range);
-            } else if r.dest == "R" {
-                rejected.push(passed_range)
-            } else {
-                let (mut a, mut r) = walk_tree(passed_range, workflows.get(&r.dest).unwrap(), workflows);
+        assert_eq!(4000u128.pow(4), table.iter().map(|d| d.region.volume()).sum::<u128>());
+        assert!(table.iter().tuple_combinations().all(|(a, b)| a.region.intersection(&b.region).is_none()));
 
-                approved.append(&mut a);
-                rejected.append(&mut r);
-            }
+        let compiled = CompiledWorkflows::compile(&workflows, "in").unwrap();
+        for p in &parts {
+            let row = table.iter().filter(|d| d.region.contains(&p.ratings)).exactly_one().unwrap();
+            assert_eq!(compiled.evaluate(p).unwrap(), row.verdict);
         }
 
-        return (approved, rejected);
+        let first = table.iter().find(|d| d.region.contains(&parts[0].ratings)).unwrap();
+        let steps = [("in", 1), ("qqz", 0), ("qs", 1), ("lnx", 0)]
+            .map(|(workflow, rule)| Step { workflow: workflow.to_owned(), rule });
+        assert_eq!(steps.to_vec(), first.trace);
     }
 
-    fn apply_p1(input: &str) -> i64 {
-        let (workflows, parts) = parse_data(input);
-
-        let mut accepted = vec![];
-        let mut rejected = vec![];
-
-
-        for p in parts {
-            let mut wf = workflows.get("in").unwrap();
-            loop {
-                let matched_rule = wf
-                    .iter()
-                    .find_or_first(|r| r.matches(&p))
-                    .unwrap();
-
-                match matched_rule.dest.as_str() {
-                    "A" => {
-                        accepted.push(p);
-                        break;
-                    }
-                    "R" => {
-                        rejected.push(p);
-                        break;
-                    }
-                    d => wf = workflows.get(d).unwrap()
-                }
-            }
-        }
+    #[test]
+    fn simplify_merges_identical_workflows() {
+        let (workflows, _) = parse_system("in{x<10:a,m<5:b,R}\na{s>3:A,R}\nb{s>3:A,R}").unwrap();
+        let simple = simplify(&workflows, "in", &xmas());
 
-        let result = accepted
-            .iter()
-            .map(|p| p.x + p.m + p.a + p.s)
-            .sum();
+        let (expected, _) = parse_system("in{x<10:a,m<5:a,R}\na{s>3:A,R}").unwrap();
+        assert_eq!(expected.workflows, simple.workflows);
 
-        return result;
+        // nothing left to drop, fold, forward or merge
+        assert_eq!(expected.workflows, simplify(&expected, "in", &xmas()).workflows);
+        assert_eq!(count_accepted(&workflows, &xmas()), count_accepted(&simple, &xmas()));
     }
 
     #[test]
-    fn sample_1_p1() {
-        let data = SAMPLE_1;
-
-
-        let results = apply_p1(data);
-        println!("Answer: {results:?}");
+    fn simplify_sample() {
+        let (workflows, parts) = parse_system(SAMPLE_1).unwrap();
+        let simple = simplify(&workflows, "in", &xmas());
+
+        assert_eq!(
+            vec!["px", "pv", "rfg", "qkq", "crn", "in", "qqz", "hdj"],
+            simple.workflows.iter().map(|w| w.name.as_str()).collect_vec()
+        );
+        assert_eq!(Target::Accept, simple.get("qqz").unwrap().rules[0].target);
+        assert_eq!(Target::Reject, simple.get("rfg").unwrap().rules[0].target);
+        assert!(analyze(&simple, "in", &xmas()).is_empty());
+
+        assert_eq!(count_accepted(&workflows, &xmas()), count_accepted(&simple, &xmas()));
+        let before = CompiledWorkflows::compile(&workflows, "in").unwrap();
+        let after = CompiledWorkflows::compile(&simple, "in").unwrap();
+        for p in &parts {
+            assert_eq!(before.evaluate(p), after.evaluate(p));
+        }
     }
 
     #[test]
-    fn sample_1_p2() {
-        let data = SAMPLE_1;
-
-        let results = apply_p2(data);
-        println!("Answer: {results:?}");
-
-
-        // 167409079868000  // Correct Answer
-        // 167409079868000
+    fn decision_table_csv() {
+        let (workflows, _) = parse_system("in{x<10:A,y==3:mid,R}\nmid{x>15:A,R}").unwrap();
+        let x = Attribute::X;
+        let y = Attribute::from_name("y");
+        let domain = Hyperrect::new([(x.clone(), (1, 20)), (y.clone(), (1, 5))]);
+        let table = decision_table(&workflows, "in", &domain).unwrap();
+
+        let expected = "x_min,x_max,y_min,y_max,verdict,trace
+1,9,1,5,A,in:0
+16,20,3,3,A,in:1>mid:0
+10,15,3,3,R,in:1>mid:1
+10,20,1,2,R,in:2
+10,20,4,5,R,in:2";
+        let csv = to_csv(&table, &[x, y]);
+        assert_eq!(expected.lines().collect_vec(), csv.lines().collect_vec());
+
+        let (cyclic, _) = parse_system("in{x<5:in,A}").unwrap();
+        assert!(decision_table(&cyclic, "in", &domain).is_err());
     }
 
     #[test]
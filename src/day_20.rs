@@ -1,12 +1,12 @@
 #[cfg(test)]
 mod day20 {
-    use std::any::Any;
-    use std::collections::{HashMap, VecDeque};
-
     use itertools::Itertools;
 
-    use crate::day_20::day20::PV::{HIGH, LOW};
+    use crate::circuit_analysis::{AnalysisError, binary_counters, independent_subcircuits, measure_periods, presses_until_low, to_dot};
+    use crate::circuit::{CircuitError, CONJUNCTION, Module, ModuleRegistry, parse_machine, parse_machine_with, Probe, PV};
+    use crate::circuit::PV::{HIGH, LOW};
     use crate::read_data_file;
+    use crate::vcd::TraceFilter;
 
     static SAMPLE_1: &str = r#"broadcaster -> a, b, c
 %a -> b
@@ -20,273 +20,279 @@ mod day20 {
 %b -> con
 &con -> output"#;
 
-    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-    enum PV {
-        HIGH,
-        LOW,
-    }
-
-    struct Pulse {
-        from: String,
-        to: String,
-        value: PV,
-    }
-
-    trait Module {
-        fn receive_pulse(&mut self, p: &Pulse) -> Option<PV>;
-        fn outputs(&self) -> &Vec<String>;
-        fn as_any(&mut self) -> &mut dyn Any;
-    }
+    // two counters, counting to 5 and to 3, each inverted into the hub feeding rx
+    static COUNTERS: &str = r#"broadcaster -> a0, c0
+%a0 -> a1, ha
+%a1 -> a2
+%a2 -> ha
+&ha -> a1, a0, ia
+&ia -> hub
+%c0 -> c1, hc
+%c1 -> hc
+&hc -> c0, ic
+&ic -> hub
+&hub -> rx"#;
 
-    struct FlipFlop {
-        current_value: PV,
-        output: Vec<String>,
-    }
-
-    impl Module for FlipFlop {
-        fn receive_pulse(&mut self, p: &Pulse) -> Option<PV> {
-            match p.value {
-                HIGH => None,
-                LOW => {
-                    self.current_value = if self.current_value == HIGH { LOW } else { HIGH };
-                    return Some(self.current_value);
-                }
-            }
-        }
+    fn apply_p1(input: &str) -> usize {
+        let mut machine = parse_machine(input).unwrap();
 
-        fn outputs(&self) -> &Vec<String> {
-            return &self.output;
+        for _ in 0..1000 {
+            machine.push_button();
         }
 
-        fn as_any(&mut self) -> &mut dyn Any {
-            self
-        }
+        return machine.high * machine.low;
     }
 
-    struct Conjunction {
-        current_values: HashMap<String, PV>,
-        output: Vec<String>,
+    fn apply_p2(input: &str) -> u64 {
+        let mut machine = parse_machine(input).unwrap();
+
+        return presses_until_low(&mut machine, "rx", 100_000).unwrap();
     }
 
-    impl Module for Conjunction {
-        fn receive_pulse(&mut self, p: &Pulse) -> Option<PV> {
-            self.current_values.insert(p.from.to_owned(), p.value);
+    fn presses_by_simulation(input: &str) -> usize {
+        let mut machine = parse_machine(input).unwrap();
+        let rx = machine.add_probe(&Probe::received("rx", LOW)).unwrap();
 
-            if self.current_values.values().into_iter().all(|v| *v == HIGH) {
-                return Some(LOW);
+        loop {
+            let report = machine.push_button();
+            if report.probes[rx] > 0 {
+                return machine.presses();
             }
 
-            return Some(HIGH);
-        }
-
-        fn outputs(&self) -> &Vec<String> {
-            return &self.output;
-        }
-
-        fn as_any(&mut self) -> &mut dyn Any {
-            self
+            if machine.presses() == 10000 {
+                panic!("too long");
+            }
         }
     }
 
-    struct Broadcast {
-        output: Vec<String>,
-    }
-
-    impl Module for Broadcast {
-        fn receive_pulse(&mut self, p: &Pulse) -> Option<PV> {
-            return Some(p.value);
-        }
-
-        fn outputs(&self) -> &Vec<String> {
-            return &self.output;
-        }
+    struct Inverter;
 
-        fn as_any(&mut self) -> &mut dyn Any {
-            self
+    impl Module for Inverter {
+        fn receive(&mut self, _from: usize, value: PV) -> Option<PV> {
+            return Some(if value == HIGH { LOW } else { HIGH });
         }
-    }
 
-    struct Machine {
-        modules: HashMap<String, Box<dyn Module>>,
-        counts: HashMap<PV, usize>,
+        fn reset(&mut self) {}
     }
 
-    impl Machine {
-        fn push_button(&mut self) -> HashMap<String, usize> {
-            let mut queue = VecDeque::new();
-
-            let first_pulse = Pulse {
-                from: "Button".to_owned(),
-                to: "broadcaster".to_owned(),
-                value: LOW,
-            };
-
-            self.counts.entry(first_pulse.value).and_modify(|c| *c += 1);
-            queue.push_front(first_pulse);
-
-
-            let mut tripped: HashMap<String, usize> = HashMap::from_iter([
-                ("kd".to_owned(), 0usize),
-                ("zf".to_owned(), 0usize),
-                ("vg".to_owned(), 0usize),
-                ("gs".to_owned(), 0usize),
-                ("rx".to_owned(), 0usize)
-            ]);
-
-            while let Some(pulse) = queue.pop_back() {
-
-                if pulse.value == LOW {
-                    tripped.entry(pulse.to.to_owned()).and_modify(|v| *v += 1);
-                }
-
-                if let Some(m) = self.modules.get_mut(&pulse.to) {
-                    let new_pulses = match m.receive_pulse(&pulse) {
-                        Some(pv) => m.outputs().iter().map(|to| Pulse {
-                            to: to.to_owned(),
-                            from: pulse.to.to_owned(),
-                            value: pv,
-                        }).collect_vec(),
-                        None => vec![]
-                    };
-
-                    new_pulses.into_iter().for_each(|p| {
-                        self.counts.entry(p.value).and_modify(|c| *c += 1);
-                        queue.push_front(p);
-                    });
-                }
-            }
+    #[test]
+    fn sample_1_p1() {
+        let data = SAMPLE_1;
 
-            return tripped;
-        }
+        let results = apply_p1(data);
+        println!("Answer: {results:?}");
+        assert_eq!(32000000, results);
     }
 
-    fn parse_machine(input: &str) -> Machine {
-        let mut modules = input
-            .lines()
-            .map(|l| {
-                let (prefix, suffix) = l.split(" -> ").collect_tuple().unwrap();
-
-                let outputs = suffix.split(",").into_iter().map(|s| s.trim().to_owned()).collect_vec();
-
-                let pair: (String, Box<dyn Module>) = match prefix.chars().next().unwrap() {
-                    '%' => {
-                        let name = prefix[1..].to_owned();
-                        (
-                            name,
-                            Box::new(FlipFlop {
-                                current_value: LOW,
-                                output: outputs,
-                            })
-                        )
-                    }
-                    '&' => {
-                        let name = prefix[1..].to_owned();
-                        (
-                            name,
-                            Box::new(Conjunction {
-                                current_values: HashMap::new(),
-                                output: outputs,
-                            })
-                        )
-                    }
-                    'b' => {
-                        let name = prefix.to_owned();
-                        (
-                            name,
-                            Box::new(Broadcast {
-                                output: outputs,
-                            })
-                        )
-                    }
-                    _ => panic!()
-                };
-
-                return pair;
-            })
-            .collect::<HashMap<String, Box<dyn Module>>>();
-
-        let mut rev_idx: HashMap<String, Vec<String>> = HashMap::new();
-
-        for (name, m) in &modules {
-            for o in m.outputs() {
-                rev_idx.entry(o.to_owned()).and_modify(|l| l.push(name.to_owned())).or_insert(vec![name.to_owned()]);
-            }
-        }
-
-        for (name, m) in &mut modules {
-            if let Some(conj) = m.as_any().downcast_mut::<Conjunction>() {
-                let talks_to_me = rev_idx.get(name).unwrap();
-                for from in talks_to_me {
-                    conj.current_values.entry(from.to_owned()).or_insert(LOW);
-                }
-            }
-        }
+    #[test]
+    fn sample_2_p1() {
+        let data = SAMPLE_2;
 
-        return Machine {
-            counts: HashMap::from_iter([(HIGH, 0), (LOW, 0)]),
-            modules,
-        };
+        let results = apply_p1(data);
+        println!("Answer: {results:?}");
+        assert_eq!(11687500, results);
     }
 
-    fn apply_p1(input: &str) -> usize {
-        let mut machine = parse_machine(input);
-
-        for _ in 0..1000 {
-            machine.push_button();
-        }
-
-        machine.counts.get(&HIGH).unwrap() * machine.counts.get(&LOW).unwrap()
+    #[test]
+    fn event_log() {
+        let mut machine = parse_machine(SAMPLE_1).unwrap();
+        machine.enable_log();
+        machine.push_button();
+
+        let log = machine.log().unwrap();
+        let lines = log.iter()
+            .map(|e| format!("{} -{}-> {}", machine.name(e.from), if e.value == HIGH { "high" } else { "low" }, machine.name(e.to)))
+            .collect_vec();
+        assert_eq!(vec![
+            "button -low-> broadcaster",
+            "broadcaster -low-> a",
+            "broadcaster -low-> b",
+            "broadcaster -low-> c",
+            "a -high-> b",
+            "b -high-> c",
+            "c -high-> inv",
+            "inv -low-> a",
+            "a -low-> b",
+            "b -low-> c",
+            "c -low-> inv",
+            "inv -high-> a",
+        ], lines);
+        assert_eq!((0..12).collect_vec(), log.iter().map(|e| e.seq).collect_vec());
+
+        // the same circuit always produces the same log
+        let mut again = parse_machine(SAMPLE_1).unwrap();
+        again.enable_log();
+        again.push_button();
+        assert_eq!(log, again.log().unwrap());
     }
 
-    fn apply_p2(input: &str) -> usize {
-        let mut machine = parse_machine(input);
-
-        let mut i = 0usize;
-
-        loop {
-            let tripped = machine.push_button();
-            i = i + 1;
-
-            for (port, cnt) in &tripped {
-                if *cnt > 0 {
-                    println!("Port: {port} fired: {cnt} times on push: {i}");
-                }
-            }
-
-
-            if *tripped.get("rx").unwrap() > 0 {
-                return i;
-            }
-
-            if i == 10000 {
-                panic!("too long");
-            }
-        }
+    #[test]
+    fn probes() {
+        let mut machine = parse_machine(SAMPLE_2).unwrap();
+        let output_high = machine.add_probe(&Probe::received("output", HIGH)).unwrap();
+        let output_low = machine.add_probe(&Probe::received("output", LOW)).unwrap();
+        let a_sent = machine.add_probe(&Probe { from: Some("a".to_owned()), ..Probe::default() }).unwrap();
+        let any_low = machine.add_probe(&Probe { value: Some(LOW), ..Probe::default() }).unwrap();
+
+        let reports = (0..4).map(|_| machine.push_button()).collect_vec();
+        assert_eq!(vec![1, 1, 1, 1], reports.iter().map(|r| r.probes[output_high]).collect_vec());
+        assert_eq!(vec![1, 0, 1, 0], reports.iter().map(|r| r.probes[output_low]).collect_vec());
+        assert_eq!(vec![2, 2, 2, 2], reports.iter().map(|r| r.probes[a_sent]).collect_vec());
+        assert_eq!(reports.iter().map(|r| r.low).collect_vec(), reports.iter().map(|r| r.probes[any_low]).collect_vec());
+
+        assert_eq!(Err(CircuitError::UnknownModule { name: "nope".to_owned() }), machine.add_probe(&Probe::sent("nope", HIGH)));
+
+        machine.reset();
+        assert_eq!(reports[0], machine.push_button());
     }
 
     #[test]
-    fn sample_1_p1() {
-        let data = SAMPLE_1;
+    fn registry() {
+        let mut registry = ModuleRegistry::standard();
+        registry.register_prefix('!', "inverter", || Box::new(Inverter));
+
+        let mut machine = parse_machine_with("broadcaster -> n\n!n -> out", &registry).unwrap();
+        assert_eq!("inverter", machine.kind(machine.index("n").unwrap()));
+        assert_eq!("sink", machine.kind(machine.index("out").unwrap()));
+        let out = machine.add_probe(&Probe::received("out", HIGH)).unwrap();
+        assert_eq!(1, machine.push_button().probes[out]);
+
+        assert_eq!(Err(CircuitError::UnknownKind { line: 2, declared: "!n".to_owned() }), parse_machine("broadcaster -> n\n!n -> out").map(|_| ()));
+        assert_eq!(Err(CircuitError::Malformed { line: 1, text: "broadcaster a".to_owned() }), parse_machine("broadcaster a").map(|_| ()));
+        assert_eq!(Err(CircuitError::Duplicate { line: 2, name: "a".to_owned() }), parse_machine("%a -> b\n&a -> c").map(|_| ()));
+    }
 
+    #[test]
+    fn counters_p2() {
+        let machine = parse_machine(COUNTERS).unwrap();
+        let (hub, subcircuits) = independent_subcircuits(&machine, "rx").unwrap();
+        assert_eq!("hub", machine.name(hub));
+        let names = subcircuits.iter()
+            .map(|s| (machine.name(s.output), s.modules.iter().map(|m| machine.name(*m)).collect_vec()))
+            .collect_vec();
+        assert_eq!(vec![
+            ("ia", vec!["a0", "a1", "a2", "ha", "ia"]),
+            ("ic", vec!["c0", "c1", "hc", "ic"]),
+        ], names);
+
+        assert_eq!(15, apply_p2(COUNTERS));
+        assert_eq!(15, presses_by_simulation(COUNTERS));
+    }
 
-        let results = apply_p1(data);
-        println!("Answer: {results:?}");
+    #[test]
+    fn counters_not_decomposable() {
+        let shared = COUNTERS.replace("&hc -> c0, ic", "&hc -> c0, ic, a1");
+        let machine = parse_machine(&shared).unwrap();
+        assert_eq!(
+            Err(AnalysisError::NotDecomposable { reason: "c0 feeds more than one input of hub".to_owned() }),
+            independent_subcircuits(&machine, "rx").map(|_| ())
+        );
+
+        let machine = parse_machine(SAMPLE_2).unwrap();
+        assert_eq!(
+            Err(AnalysisError::UnknownModule { name: "rx".to_owned() }),
+            independent_subcircuits(&machine, "rx").map(|_| ())
+        );
+        assert!(matches!(independent_subcircuits(&machine, "con"), Err(AnalysisError::NotDecomposable { .. })));
+        assert!(matches!(independent_subcircuits(&machine, "output"), Err(AnalysisError::NotDecomposable { .. })));
     }
 
     #[test]
-    fn sample_2_p1() {
-        let data = SAMPLE_2;
+    fn vcd_trace() {
+        let mut machine = parse_machine(SAMPLE_1).unwrap();
+        machine.record_trace(&TraceFilter::Modules(vec!["inv".to_owned(), "a".to_owned()])).unwrap();
+        machine.push_button();
+
+        let vcd = machine.trace().unwrap().to_vcd();
+        assert_eq!(r#"$version day 20 pulse trace $end
+$timescale 1ns $end
+$scope module circuit $end
+$var integer 32 ! press $end
+$var wire 1 " a $end
+$var wire 1 # inv $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+b0 !
+0"
+0#
+$end
+#1
+b1 !
+#5
+1"
+#9
+0"
+#12
+1#
+"#, vcd);
+
+        let path = std::env::temp_dir().join("day_20_trace.vcd");
+        machine.trace().unwrap().write_to(&path).unwrap();
+        assert_eq!(vcd, std::fs::read_to_string(&path).unwrap());
+
+        machine.push_button();
+        assert!(machine.trace().unwrap().to_vcd().contains("1#\n#13\nb10 !\n"));
+
+        machine.record_trace(&TraceFilter::Kinds(vec![CONJUNCTION])).unwrap();
+        machine.push_button();
+        let vcd = machine.trace().unwrap().to_vcd();
+        assert!(vcd.contains("$var wire 1 \" inv $end"));
+        assert!(!vcd.contains(" a $end"));
+
+        assert_eq!(
+            Err(CircuitError::UnknownModule { name: "nope".to_owned() }),
+            machine.record_trace(&TraceFilter::Modules(vec!["nope".to_owned()]))
+        );
+    }
 
+    #[test]
+    fn dot_export() {
+        let machine = parse_machine(SAMPLE_2).unwrap();
+        assert_eq!(r#"digraph circuit {
+    "broadcaster" [shape=doublecircle];
+    "a" [shape=box];
+    "inv" [shape=diamond];
+    "b" [shape=box];
+    "con" [shape=diamond];
+    "output" [shape=plaintext];
+    "broadcaster" -> "a";
+    "a" -> "inv";
+    "a" -> "con";
+    "inv" -> "b";
+    "b" -> "con";
+    "con" -> "output";
+}"#, to_dot(&machine));
+    }
 
-        let results = apply_p1(data);
-        println!("Answer: {results:?}");
+    #[test]
+    fn counters_structure() {
+        let mut machine = parse_machine(COUNTERS).unwrap();
+        let counters = binary_counters(&machine);
+        let found = counters.iter()
+            .map(|c| (machine.name(c.hub), c.bits.iter().map(|b| machine.name(*b)).collect_vec(), c.pattern.as_str(), c.period))
+            .collect_vec();
+        assert_eq!(vec![
+            ("ha", vec!["a0", "a1", "a2"], "101", 5),
+            ("hc", vec!["c0", "c1"], "11", 3),
+        ], found);
+
+        // the periods read off the wiring agree with simulation
+        let (hub, subcircuits) = independent_subcircuits(&machine, "rx").unwrap();
+        let measured = measure_periods(&mut machine, hub, &subcircuits, 100).unwrap();
+        assert_eq!(vec![5, 3], measured.iter().map(|p| p.period).collect_vec());
+
+        // a chain without the hub's reset wiring isn't a counter
+        let broken = parse_machine(&COUNTERS.replace("&ha -> a1, a0, ia", "&ha -> a0, ia")).unwrap();
+        assert_eq!(1, binary_counters(&broken).len());
+        assert!(binary_counters(&parse_machine(SAMPLE_1).unwrap()).is_empty());
     }
 
     #[test]
     fn part_1() {
         let data = read_data_file(20, "input.txt");
 
-
         let results = apply_p1(&data);
         println!("Answer: {results:?}");
     }
@@ -298,4 +304,4 @@ mod day20 {
         let results = apply_p2(&data);
         println!("Answer: {results:?}");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,171 @@
+use itertools::Itertools;
+
+use crate::hyperrect::Hyperrect;
+use crate::workflow::{Attribute, Rule, Target, Verdict, Workflow, WorkflowSet};
+use crate::workflow_analysis::{analyze, Issue, live_rules};
+
+/// A rule that matched on the way to a verdict; every earlier rule in that workflow didn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub workflow: String,
+    pub rule: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub region: Hyperrect,
+    pub verdict: Verdict,
+    pub trace: Vec<Step>,
+}
+
+/// Splits `domain` into disjoint boxes, each sent to a single verdict by a single chain of
+/// rules. Refuses workflow sets the analyzer finds fatal issues in.
+pub fn decision_table(set: &WorkflowSet, start: &str, domain: &Hyperrect) -> Result<Vec<Decision>, Vec<Issue>> {
+    let fatal = analyze(set, start, domain)
+        .into_iter()
+        .filter(|i| i.is_fatal())
+        .collect_vec();
+    if !fatal.is_empty() {
+        return Err(fatal);
+    }
+
+    let mut table = vec![];
+    walk(set, set.get(start).unwrap(), domain.clone(), &mut vec![], &mut table);
+
+    return Ok(table);
+}
+
+fn walk(set: &WorkflowSet, workflow: &Workflow, region: Hyperrect, trace: &mut Vec<Step>, table: &mut Vec<Decision>) {
+    // `==` can split the region that falls through into two boxes
+    let mut remaining = vec![region];
+
+    for (i, r) in workflow.rules.iter().enumerate() {
+        let passed = match &r.condition {
+            None => remaining.drain(..).collect_vec(),
+            Some(c) => {
+                let mut passed = vec![];
+                let mut failed = vec![];
+                for b in remaining {
+                    let (pass, fail) = b.split_by(c);
+                    passed.extend(pass);
+                    failed.extend(fail);
+                }
+                remaining = failed;
+                passed
+            }
+        };
+
+        for p in passed {
+            trace.push(Step { workflow: workflow.name.clone(), rule: i });
+            match &r.target {
+                Target::Accept => table.push(Decision { region: p, verdict: Verdict::Accept, trace: trace.clone() }),
+                Target::Reject => table.push(Decision { region: p, verdict: Verdict::Reject, trace: trace.clone() }),
+                Target::Workflow(name) => walk(set, set.get(name).unwrap(), p, trace, table),
+            }
+            trace.pop();
+        }
+    }
+}
+
+/// One row per decision: the bounds of each attribute, the verdict, and the trace as
+/// `workflow:rule` steps joined with `>`.
+pub fn to_csv(table: &[Decision], attributes: &[Attribute]) -> String {
+    let header = attributes
+        .iter()
+        .flat_map(|a| [format!("{}_min", a.name()), format!("{}_max", a.name())])
+        .chain(["verdict".to_owned(), "trace".to_owned()])
+        .join(",");
+
+    let rows = table.iter().map(|d| {
+        attributes
+            .iter()
+            .flat_map(|a| {
+                let (lo, hi) = d.region.bounds(a);
+                [lo.to_string(), hi.to_string()]
+            })
+            .chain([
+                match d.verdict {
+                    Verdict::Accept => "A".to_owned(),
+                    Verdict::Reject => "R".to_owned(),
+                },
+                d.trace.iter().map(|s| format!("{}:{}", s.workflow, s.rule)).join(">"),
+            ])
+            .join(",")
+    });
+
+    return std::iter::once(header).chain(rows).join("\n");
+}
+
+/// An equivalent workflow set for parts inside `domain`, assuming `analyze` finds nothing
+/// fatal in `set`. Until nothing changes, it drops rules that can't fire, folds a
+/// conditional rule into a final catch-all with the same target, routes around workflows
+/// that always go to one target, merges workflows with identical rules, and removes
+/// workflows no longer reachable from `start`.
+///
+/// The result is minimal only with respect to those rewrites: rules are never reordered
+/// or re-split, so workflows that agree on every part but test in a different order are
+/// kept apart.
+pub fn simplify(set: &WorkflowSet, start: &str, domain: &Hyperrect) -> WorkflowSet {
+    let mut workflows = set.workflows.clone();
+
+    loop {
+        let before = workflows.clone();
+
+        for w in &mut workflows {
+            let live = live_rules(w, domain);
+            w.rules = w.rules.iter().zip(live).filter(|(_, l)| *l).map(|(r, _)| r.clone()).collect_vec();
+
+            while let [.., prev, Rule { condition: None, target }] = w.rules.as_slice() {
+                if prev.target != *target {
+                    break;
+                }
+                w.rules.remove(w.rules.len() - 2);
+            }
+        }
+
+        let forwards = workflows
+            .iter()
+            .filter(|w| w.name != start)
+            .filter_map(|w| match w.rules.as_slice() {
+                [Rule { condition: None, target }] => Some((w.name.clone(), target.clone())),
+                _ => None
+            })
+            .collect_vec();
+
+        // a workflow with the same rules as another can stand in for it; the start is
+        // always the one kept
+        let merges = workflows
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| w.name != start)
+            .filter_map(|(i, w)| {
+                workflows.iter()
+                    .find(|k| k.name == start && k.rules == w.rules)
+                    .or_else(|| workflows[..i].iter().find(|k| k.rules == w.rules))
+                    .map(|k| (w.name.clone(), Target::Workflow(k.name.clone())))
+            })
+            .collect_vec();
+
+        for w in &mut workflows {
+            for r in &mut w.rules {
+                if let Some((_, target)) = forwards.iter().chain(&merges).find(|(name, _)| r.target == Target::Workflow(name.clone())) {
+                    r.target = target.clone();
+                }
+            }
+        }
+
+        let current = WorkflowSet::new(workflows.clone());
+        let unreachable = analyze(&current, start, domain)
+            .into_iter()
+            .filter_map(|i| match i {
+                Issue::Unreachable { workflow } => Some(workflow),
+                _ => None
+            })
+            .collect_vec();
+        workflows.retain(|w| !unreachable.contains(&w.name));
+
+        if workflows == before {
+            return WorkflowSet::new(workflows);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::{HashSet, VecDeque};
+
+use itertools::Itertools;
+use num::integer::lcm;
+
+use crate::grid::{DIR, Grid, Point};
+use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+
+// growth has to settle within this many periods for `extrapolate` to trust it
+const MAX_PERIODS: usize = 32;
+
+/// A garden map with its start, either on its own or tiled infinitely in every direction.
+pub struct Garden {
+    grid: Grid,
+    start: Point,
+}
+
+/// Reachable plots after `residue + k * period` steps, quadratic in `k` from `first` on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Quadratic {
+    pub period: usize,
+    pub residue: usize,
+    pub first: usize,
+    pub values: [u64; 3],
+}
+
+impl Quadratic {
+    /// Only meaningful for `k >= first`.
+    pub fn at(&self, k: u64) -> u64 {
+        let [a, b, c] = self.values.map(|v| v as i128);
+        let n = k as i128 - self.first as i128;
+        return (a + n * (b - a) + n * (n - 1) / 2 * (c - 2 * b + a)) as u64;
+    }
+}
+
+fn step((x, y): (i64, i64), dir: DIR) -> (i64, i64) {
+    return match dir {
+        NORTH => (x, y - 1),
+        SOUTH => (x, y + 1),
+        EAST => (x + 1, y),
+        WEST => (x - 1, y),
+    };
+}
+
+impl Garden {
+    pub fn from_grid(grid: Grid) -> Option<Garden> {
+        let start = grid.find_first('S')?;
+        return Some(Garden { grid, start });
+    }
+
+    fn is_plot(&self, (x, y): (i64, i64), tiled: bool) -> bool {
+        let (w, h) = (self.grid.x_size as i64, self.grid.y_size as i64);
+        let inside = (0..w).contains(&x) && (0..h).contains(&y);
+        if !tiled && !inside {
+            return false;
+        }
+
+        return *self.grid.get(x.rem_euclid(w) as usize, y.rem_euclid(h) as usize).unwrap() != '#';
+    }
+
+    /// How many plots lie at each walking distance from the start, up to `limit`.
+    fn distance_counts(&self, limit: usize, tiled: bool) -> Vec<u64> {
+        let start = (self.start.x as i64, self.start.y as i64);
+        let mut counts = vec![0u64; limit + 1];
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0usize)]);
+
+        while let Some((p, d)) = queue.pop_front() {
+            counts[d] += 1;
+            if d == limit {
+                continue;
+            }
+
+            for dir in [NORTH, SOUTH, EAST, WEST] {
+                let n = step(p, dir);
+                if self.is_plot(n, tiled) && seen.insert(n) {
+                    queue.push_back((n, d + 1));
+                }
+            }
+        }
+
+        return counts;
+    }
+
+    /// Plots reachable in exactly `steps` steps are those at a distance of at most `steps`
+    /// with the same parity, since any of them can be reached by stepping back and forth.
+    fn reachable_from_counts(counts: &[u64], steps: usize) -> u64 {
+        return counts.iter().take(steps + 1).skip(steps % 2).step_by(2).sum();
+    }
+
+    /// Plots reachable in exactly `steps` steps without leaving the map.
+    pub fn reachable(&self, steps: usize) -> u64 {
+        return Garden::reachable_from_counts(&self.distance_counts(steps, false), steps);
+    }
+
+    /// Plots reachable in exactly `steps` steps on the tiled map, by walking every one.
+    pub fn reachable_tiled(&self, steps: usize) -> u64 {
+        return Garden::reachable_from_counts(&self.distance_counts(steps, true), steps);
+    }
+
+    /// Walks the tiled map for up to `periods` periods past `residue` and looks for three
+    /// consecutive second differences that agree.
+    fn growth_within(&self, residue: usize, periods: usize) -> Option<Quadratic> {
+        let period = lcm(self.grid.x_size, self.grid.y_size);
+        let counts = self.distance_counts(residue + periods * period, true);
+        let samples = (0..=periods)
+            .map(|k| Garden::reachable_from_counts(&counts, residue + k * period) as i128)
+            .collect_vec();
+        let second = samples.iter().tuple_windows().map(|(a, b, c)| c - 2 * b + a).collect_vec();
+
+        let first = second.iter().tuple_windows().position(|(a, b, c)| a == b && b == c)?;
+
+        return Some(Quadratic {
+            period,
+            residue,
+            first,
+            values: [samples[first] as u64, samples[first + 1] as u64, samples[first + 2] as u64],
+        });
+    }
+
+    /// The quadratic that step counts `residue` apart by whole periods follow, once the
+    /// walk has spread past the first few copies of the map.
+    pub fn growth(&self, residue: usize) -> Option<Quadratic> {
+        let mut periods = 8;
+        loop {
+            if let Some(q) = self.growth_within(residue, periods) {
+                return Some(q);
+            }
+            if periods >= MAX_PERIODS {
+                return None;
+            }
+            periods *= 2;
+        }
+    }
+
+    /// Plots reachable in exactly `steps` steps on the tiled map, walking only as far as
+    /// it takes to find the quadratic growth.
+    pub fn extrapolate(&self, steps: u64) -> Option<u64> {
+        let period = lcm(self.grid.x_size, self.grid.y_size) as u64;
+        let residue = (steps % period) as usize;
+        let growth = self.growth(residue)?;
+
+        let k = steps / period;
+        if k < growth.first as u64 + 3 {
+            return Some(self.reachable_tiled(steps as usize));
+        }
+
+        return Some(growth.at(k));
+    }
+}
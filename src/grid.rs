@@ -30,6 +30,7 @@ impl Point {
 }
 
 
+#[derive(Clone)]
 pub struct Grid {
     data: Vec<Vec<char>>,
     pub x_size: usize,
@@ -45,9 +46,9 @@ impl Grid {
         self.data[y][x] = c;
     }
 
-    // pub fn set_point(&mut self, p: &Point, c: char) {
-    //     self.set(p.x, p.y, c);
-    // }
+    pub fn set_point(&mut self, p: &Point, c: char) {
+        self.set(p.x, p.y, c);
+    }
 
     pub fn get_point(&self, point: &Point) -> Option<&char> {
         return self.data.get(point.y).map(|r| r.get(point.x)).flatten();
@@ -203,15 +204,15 @@ impl Grid {
         return None;
     }
 
-    // pub fn from_size(x_size: usize, y_size: usize) -> Grid {
-    //     let data = vec![vec!['.'; x_size]; y_size];
-    //
-    //     return Grid {
-    //         data,
-    //         x_size,
-    //         y_size
-    //     };
-    // }
+    pub fn from_size(x_size: usize, y_size: usize) -> Grid {
+        let data = vec![vec!['.'; x_size]; y_size];
+
+        return Grid {
+            data,
+            x_size,
+            y_size
+        };
+    }
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, Debug, Copy, Ord, PartialOrd)]
@@ -220,4 +221,18 @@ pub enum DIR {
     SOUTH,
     EAST,
     WEST,
-}
\ No newline at end of file
+}
+
+impl DIR {
+    pub const ALL: [DIR; 4] = [NORTH, SOUTH, EAST, WEST];
+
+    /// Position in `DIR::ALL`, for tables indexed by direction.
+    pub fn index(&self) -> usize {
+        match self {
+            NORTH => 0,
+            SOUTH => 1,
+            EAST => 2,
+            WEST => 3
+        }
+    }
+}
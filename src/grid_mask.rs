@@ -0,0 +1,131 @@
+use crate::grid::{DIR, Grid};
+use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+
+/// A fixed-length row of bits packed into `u64` words, so a line of up to 64 cells is a
+/// single word and wider lines just use more of them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitRow {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitRow {
+    pub fn new(len: usize) -> BitRow {
+        return BitRow {
+            len,
+            words: vec![0; len.div_ceil(64)],
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        return self.words[i / 64] & (1 << (i % 64)) != 0;
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) {
+        if value {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    pub fn count_ones(&self) -> u32 {
+        return self.words.iter().map(|w| w.count_ones()).sum();
+    }
+
+    pub fn union_with(&mut self, other: &BitRow) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+
+    /// Slides every set bit towards index 0, or towards the end if `to_end`, until it meets
+    /// the edge, a bit set in `walls`, or a bit that has already stopped.
+    pub fn rolled(&self, walls: &BitRow, to_end: bool) -> BitRow {
+        let at = |i: usize| if to_end { self.len - 1 - i } else { i };
+        let mut result = BitRow::new(self.len);
+        let mut free = 0;
+
+        for i in 0..self.len {
+            let p = at(i);
+            if walls.get(p) {
+                free = i + 1;
+            } else if self.get(p) {
+                result.set(at(free), true);
+                free += 1;
+            }
+        }
+
+        return result;
+    }
+
+    /// Number of positions where the two rows differ.
+    pub fn hamming(&self, other: &BitRow) -> u32 {
+        return self.words
+            .iter()
+            .zip(&other.words)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+    }
+}
+
+/// Where one symbol sits in a grid, as a bit row per grid row and per grid column.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GridMask {
+    pub rows: Vec<BitRow>,
+    pub cols: Vec<BitRow>,
+}
+
+impl GridMask {
+    pub fn from_grid(grid: &Grid, symbol: char) -> GridMask {
+        let mut rows = vec![BitRow::new(grid.x_size); grid.y_size];
+        let mut cols = vec![BitRow::new(grid.y_size); grid.x_size];
+
+        for (y, row) in rows.iter_mut().enumerate() {
+            for (x, col) in cols.iter_mut().enumerate() {
+                if *grid.get(x, y).unwrap() == symbol {
+                    row.set(x, true);
+                    col.set(y, true);
+                }
+            }
+        }
+
+        return GridMask { rows, cols };
+    }
+
+    fn from_rows(rows: Vec<BitRow>) -> GridMask {
+        let mut cols = vec![BitRow::new(rows.len()); rows.first().map_or(0, |r| r.len())];
+        for (y, row) in rows.iter().enumerate() {
+            for (x, col) in cols.iter_mut().enumerate() {
+                col.set(y, row.get(x));
+            }
+        }
+
+        return GridMask { rows, cols };
+    }
+
+    fn from_cols(cols: Vec<BitRow>) -> GridMask {
+        let transposed = GridMask::from_rows(cols);
+        return GridMask { rows: transposed.cols, cols: transposed.rows };
+    }
+
+    /// Rolls the set cells as far towards `dir` as they go, stopping at the edge, at cells
+    /// set in `walls`, or against cells that stopped first. Works on the bit rows and
+    /// columns directly, without going back through a `Grid`.
+    pub fn tilt(&self, walls: &GridMask, dir: &DIR) -> GridMask {
+        let roll = |lines: &Vec<BitRow>, wall_lines: &Vec<BitRow>, to_end: bool| {
+            return lines.iter().zip(wall_lines).map(|(l, w)| l.rolled(w, to_end)).collect::<Vec<BitRow>>();
+        };
+
+        return match dir {
+            NORTH => GridMask::from_cols(roll(&self.cols, &walls.cols, false)),
+            SOUTH => GridMask::from_cols(roll(&self.cols, &walls.cols, true)),
+            WEST => GridMask::from_rows(roll(&self.rows, &walls.rows, false)),
+            EAST => GridMask::from_rows(roll(&self.rows, &walls.rows, true)),
+        };
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap};
+
+use itertools::Itertools;
+
+use crate::workflow::{Attribute, Condition, Interval};
+
+const UNBOUNDED: Interval = (i64::MIN, i64::MAX);
+
+/// An axis-aligned box of parts: an inclusive interval per attribute. Attributes the box
+/// doesn't list are unconstrained.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hyperrect {
+    bounds: BTreeMap<Attribute, Interval>,
+}
+
+impl Hyperrect {
+    pub fn new<I: IntoIterator<Item=(Attribute, Interval)>>(bounds: I) -> Hyperrect {
+        return Hyperrect { bounds: bounds.into_iter().collect() };
+    }
+
+    /// Every attribute in `attributes` ranging over the same interval.
+    pub fn uniform(attributes: &[Attribute], interval: Interval) -> Hyperrect {
+        return Hyperrect::new(attributes.iter().map(|a| (a.clone(), interval)));
+    }
+
+    pub fn bounds(&self, attribute: &Attribute) -> Interval {
+        return *self.bounds.get(attribute).unwrap_or(&UNBOUNDED);
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item=&Attribute> {
+        return self.bounds.keys();
+    }
+
+    pub fn with(&self, attribute: &Attribute, interval: Interval) -> Hyperrect {
+        let mut result = self.clone();
+        result.bounds.insert(attribute.clone(), interval);
+        return result;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bounds.values().any(|(lo, hi)| lo > hi);
+    }
+
+    /// Number of integer points inside, saturating for huge boxes.
+    pub fn volume(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+
+        return self.bounds
+            .values()
+            .map(|(lo, hi)| (*hi as i128 - *lo as i128 + 1) as u128)
+            .fold(1u128, |acc, n| acc.saturating_mul(n));
+    }
+
+    pub fn contains(&self, ratings: &HashMap<Attribute, i64>) -> bool {
+        return self.bounds
+            .iter()
+            .all(|(a, (lo, hi))| ratings.get(a).is_some_and(|v| lo <= v && v <= hi));
+    }
+
+    /// The parts below `threshold` on `attribute`, and the parts at or above it.
+    pub fn split_at(&self, attribute: &Attribute, threshold: i64) -> (Option<Hyperrect>, Option<Hyperrect>) {
+        let (lo, hi) = self.bounds(attribute);
+        let below = threshold.checked_sub(1).map(|t| self.with(attribute, (lo, hi.min(t))));
+        let above = self.with(attribute, (lo.max(threshold), hi));
+
+        return (
+            below.filter(|b| !b.is_empty()),
+            Some(above).filter(|b| !b.is_empty()),
+        );
+    }
+
+    /// The part matching `condition` and the disjoint pieces that don't.
+    pub fn split_by(&self, condition: &Condition) -> (Option<Hyperrect>, Vec<Hyperrect>) {
+        let attribute = &condition.attribute;
+        let (pass, fail) = condition.comparison.split(self.bounds(attribute), condition.value);
+
+        return (
+            pass.map(|p| self.with(attribute, p)),
+            fail.into_iter().map(|f| self.with(attribute, f)).collect_vec(),
+        );
+    }
+
+    pub fn intersection(&self, other: &Hyperrect) -> Option<Hyperrect> {
+        let result = Hyperrect::new(
+            self.attributes()
+                .chain(other.attributes())
+                .unique()
+                .map(|a| {
+                    let ((lo1, hi1), (lo2, hi2)) = (self.bounds(a), other.bounds(a));
+                    (a.clone(), (lo1.max(lo2), hi1.min(hi2)))
+                })
+        );
+
+        return Some(result).filter(|r| !r.is_empty());
+    }
+
+    /// `self` minus `other` as disjoint boxes: peel off the slabs outside `other` one
+    /// attribute at a time, leaving the overlap behind.
+    pub fn subtract(&self, other: &Hyperrect) -> Vec<Hyperrect> {
+        if self.is_empty() {
+            return vec![];
+        }
+        if self.intersection(other).is_none() {
+            return vec![self.clone()];
+        }
+
+        let mut pieces = vec![];
+        let mut rest = self.clone();
+        for a in other.attributes() {
+            let (lo, hi) = other.bounds(a);
+
+            let (below, inside) = rest.split_at(a, lo);
+            pieces.extend(below);
+            let (inside, above) = match hi.checked_add(1) {
+                Some(t) => inside.unwrap().split_at(a, t),
+                None => (inside, None),
+            };
+            pieces.extend(above);
+            rest = inside.unwrap();
+        }
+
+        return pieces;
+    }
+}
@@ -29,6 +29,25 @@ mod day_23;
 mod grid_graph;
 mod day_24;
 mod day_25;
+mod cycle;
+mod range_set;
+mod polygon;
+mod pipe_maze;
+mod nonogram;
+mod reflection;
+mod grid_mask;
+mod rock_platform;
+mod beam;
+mod momentum;
+mod workflow;
+mod scc;
+mod workflow_analysis;
+mod hyperrect;
+mod decision_table;
+mod circuit;
+mod circuit_analysis;
+mod vcd;
+mod garden;
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
@@ -60,4 +79,49 @@ fn shortest_path<T: Ord + Copy + Hash, F: Fn(T) -> Vec<(T, i64)>>(start: T, adj:
     }
 
     return dist;
-}
\ No newline at end of file
+}
+
+/// Dial's algorithm: same closure shape as `shortest_path`, but states are mapped to
+/// dense indexes below `state_count` and queued in per-distance buckets, which suits
+/// small non-negative integer edge weights. Unreached states are left at `i64::MAX`.
+///
+/// Also returns the index of the state each state was last relaxed from. Following those
+/// back from any reached state ends at `start`, even across zero-weight edges, since a
+/// state is only expanded once its distance is final.
+fn shortest_path_dial<T: Copy, I: Fn(T) -> usize, F: Fn(T) -> Vec<(T, i64)>>(start: T, state_count: usize, index: I, adj: F) -> (Vec<i64>, Vec<Option<usize>>) {
+    let mut dist = vec![i64::MAX; state_count];
+    let mut parent = vec![None; state_count];
+    dist[index(start)] = 0;
+
+    let mut buckets: Vec<Vec<T>> = vec![vec![start]];
+    let mut current = 0usize;
+
+    while current < buckets.len() {
+        // zero-weight edges push back into the bucket being drained
+        while let Some(t) = buckets[current].pop() {
+            let idx = index(t);
+            if dist[idx] < current as i64 {
+                continue;
+            }
+
+            for (new_t, new_dist) in adj(t) {
+                let total_dist_to_new_t = current as i64 + new_dist;
+                let new_idx = index(new_t);
+                if total_dist_to_new_t < dist[new_idx] {
+                    dist[new_idx] = total_dist_to_new_t;
+                    parent[new_idx] = Some(idx);
+
+                    let b = total_dist_to_new_t as usize;
+                    if b >= buckets.len() {
+                        buckets.resize_with(b + 1, Vec::new);
+                    }
+                    buckets[b].push(new_t);
+                }
+            }
+        }
+
+        current += 1;
+    }
+
+    return (dist, parent);
+}
@@ -0,0 +1,149 @@
+use itertools::Itertools;
+
+use crate::grid::{DIR, Grid, Point};
+use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+use crate::shortest_path_dial;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Turn {
+    Left,
+    Right,
+    Reverse,
+}
+
+fn turn(dir: &DIR, t: &Turn) -> DIR {
+    match (t, dir) {
+        (Turn::Left, NORTH) | (Turn::Right, SOUTH) | (Turn::Reverse, EAST) => WEST,
+        (Turn::Left, SOUTH) | (Turn::Right, NORTH) | (Turn::Reverse, WEST) => EAST,
+        (Turn::Left, EAST) | (Turn::Right, WEST) | (Turn::Reverse, SOUTH) => NORTH,
+        (Turn::Left, WEST) | (Turn::Right, EAST) | (Turn::Reverse, NORTH) => SOUTH,
+    }
+}
+
+/// How a mover may steer: it must go at least `min_run` cells in a line before turning
+/// (or stopping at the goal) and at most `max_run` before it has to turn.
+#[derive(Debug, Clone)]
+pub struct MovementPolicy {
+    pub min_run: usize,
+    pub max_run: usize,
+    pub turns: Vec<Turn>,
+}
+
+impl MovementPolicy {
+    pub fn crucible() -> MovementPolicy {
+        return MovementPolicy {
+            min_run: 1,
+            max_run: 3,
+            turns: vec![Turn::Left, Turn::Right],
+        };
+    }
+
+    pub fn ultra_crucible() -> MovementPolicy {
+        return MovementPolicy {
+            min_run: 4,
+            max_run: 10,
+            turns: vec![Turn::Left, Turn::Right],
+        };
+    }
+}
+
+/// Cost of entering each cell; `None` cells can't be entered.
+pub struct CostGrid {
+    pub x_size: usize,
+    pub y_size: usize,
+    grid: Grid,
+    costs: Vec<Option<u32>>,
+}
+
+impl CostGrid {
+    pub fn from_grid<F: Fn(char) -> Option<u32>>(grid: &Grid, cost: F) -> CostGrid {
+        let mut costs = vec![];
+        for y in 0..grid.y_size {
+            for x in 0..grid.x_size {
+                costs.push(cost(*grid.get(x, y).unwrap()));
+            }
+        }
+
+        return CostGrid {
+            x_size: grid.x_size,
+            y_size: grid.y_size,
+            grid: grid.clone(),
+            costs,
+        };
+    }
+
+    pub fn from_digits(grid: &Grid) -> CostGrid {
+        return CostGrid::from_grid(grid, |c| c.to_digit(10));
+    }
+
+    pub fn cost(&self, p: &Point) -> Option<u32> {
+        return self.costs[p.y * self.x_size + p.x];
+    }
+}
+
+/// Total cost of the cells entered, and every cell visited from start to goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub cost: u64,
+    pub path: Vec<Point>,
+}
+
+/// Headings and run lengths reachable in one move from heading `dir` after `run` cells.
+fn moves(policy: &MovementPolicy, dir: DIR, run: usize) -> Vec<(DIR, usize)> {
+    // run 0 stands for "not moved yet", so any heading is allowed from it
+    if run == 0 {
+        return DIR::ALL.map(|d| (d, 1)).to_vec();
+    }
+
+    let mut moves = vec![];
+    if run < policy.max_run {
+        moves.push((dir, run + 1));
+    }
+    if run >= policy.min_run {
+        moves.extend(policy.turns.iter().map(|t| (turn(&dir, t), 1)));
+    }
+
+    return moves;
+}
+
+/// Dial's algorithm over `(cell, heading, run length)`, as cell costs are small. The start
+/// cell's own cost isn't paid and the first move may go in any direction.
+pub fn find_route(grid: &CostGrid, policy: &MovementPolicy, start: Point, goal: Point) -> Option<Route> {
+    let runs = policy.max_run + 1;
+    let index = |(p, d, run): (Point, DIR, usize)| ((p.y * grid.x_size + p.x) * 4 + d.index()) * runs + run;
+
+    let (dist, parent) = shortest_path_dial(
+        (start, NORTH, 0usize),
+        grid.x_size * grid.y_size * 4 * runs,
+        index,
+        |(p, dir, run)| {
+            moves(policy, dir, run)
+                .into_iter()
+                .filter_map(|(nd, nrun)| {
+                    let np = grid.grid.try_move(&p, &nd)?;
+                    let step_cost = grid.cost(&np)?;
+                    Some(((np, nd, nrun), step_cost as i64))
+                })
+                .collect_vec()
+        },
+    );
+
+    let end = DIR::ALL
+        .into_iter()
+        .cartesian_product(policy.min_run.max(1)..runs)
+        .map(|(d, run)| index((goal, d, run)))
+        .min_by_key(|s| dist[*s])
+        .filter(|s| dist[*s] != i64::MAX)?;
+    let cost = dist[end];
+
+    let mut path = vec![];
+    let mut at = Some(end);
+    while let Some(s) = at {
+        let cell = s / runs / 4;
+        path.push(Point { x: cell % grid.x_size, y: cell / grid.x_size });
+        at = parent[s];
+    }
+    path.reverse();
+
+    return Some(Route { cost: cost as u64, path });
+}
@@ -0,0 +1,221 @@
+use itertools::Itertools;
+
+use crate::grid::Grid;
+
+/// Counting table for one line of `#`, `.` and `?` cells: `ways[i][j]` is the number of
+/// ways to fill `row[i..]` with `runs[j..]`.
+struct LineTable<'a> {
+    row: &'a [u8],
+    runs: &'a [usize],
+    dots_before: Vec<usize>,
+    ways: Vec<Vec<usize>>,
+}
+
+impl<'a> LineTable<'a> {
+    fn new(row: &'a [u8], runs: &'a [usize]) -> LineTable<'a> {
+        let n = row.len();
+        let m = runs.len();
+
+        let mut dots_before = vec![0; n + 1];
+        for i in 0..n {
+            dots_before[i + 1] = dots_before[i] + if row[i] == b'.' { 1 } else { 0 };
+        }
+
+        let mut table = LineTable {
+            row,
+            runs,
+            dots_before,
+            ways: vec![vec![0; m + 1]; n + 1],
+        };
+
+        table.ways[n][m] = 1;
+        for i in (0..n).rev() {
+            for j in (0..=m).rev() {
+                let mut total = 0;
+                if row[i] != b'#' {
+                    total += table.ways[i + 1][j];
+                }
+                if j < m && table.fits(i, runs[j]) {
+                    total += table.ways[table.after_run(i, runs[j])][j + 1];
+                }
+                table.ways[i][j] = total;
+            }
+        }
+
+        return table;
+    }
+
+    /// A run of `len` can start at `i`: no `.` inside it and no `#` straight after.
+    fn fits(&self, i: usize, len: usize) -> bool {
+        let end = i + len;
+        return end <= self.row.len()
+            && self.dots_before[end] == self.dots_before[i]
+            && (end == self.row.len() || self.row[end] != b'#');
+    }
+
+    fn after_run(&self, i: usize, len: usize) -> usize {
+        return (i + len + 1).min(self.row.len());
+    }
+
+    fn count(&self) -> usize {
+        return self.ways[0][0];
+    }
+
+    fn nth(&self, mut n: usize) -> Option<String> {
+        if n >= self.count() {
+            return None;
+        }
+
+        let mut out = String::with_capacity(self.row.len());
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.row.len() {
+            if self.row[i] != b'#' {
+                let skip = self.ways[i + 1][j];
+                if n < skip {
+                    out.push('.');
+                    i += 1;
+                    continue;
+                }
+                n -= skip;
+            }
+
+            let len = self.runs[j];
+            out.push_str(&"#".repeat(len));
+            if i + len < self.row.len() {
+                out.push('.');
+            }
+            i = self.after_run(i, len);
+            j += 1;
+        }
+
+        return Some(out);
+    }
+
+    /// For every cell, whether some arrangement puts `#` there and whether some puts `.`.
+    fn possible_cells(&self) -> Vec<(bool, bool)> {
+        let n = self.row.len();
+        let m = self.runs.len();
+
+        let mut reachable = vec![vec![false; m + 1]; n + 1];
+        reachable[0][0] = true;
+        let mut cells = vec![(false, false); n];
+
+        for i in 0..n {
+            for j in 0..=m {
+                if !reachable[i][j] {
+                    continue;
+                }
+
+                if self.row[i] != b'#' && self.ways[i + 1][j] > 0 {
+                    reachable[i + 1][j] = true;
+                    cells[i].1 = true;
+                }
+
+                if j < m && self.fits(i, self.runs[j]) {
+                    let next = self.after_run(i, self.runs[j]);
+                    if self.ways[next][j + 1] > 0 {
+                        reachable[next][j + 1] = true;
+                        for c in &mut cells[i..i + self.runs[j]] {
+                            c.0 = true;
+                        }
+                        if i + self.runs[j] < n {
+                            cells[i + self.runs[j]].1 = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        return cells;
+    }
+}
+
+pub fn count_arrangements(row: &str, runs: &[usize]) -> usize {
+    return LineTable::new(row.as_bytes(), runs).count();
+}
+
+/// Arrangements are numbered in order with `.` before `#`, so a random `n` below
+/// `count_arrangements` gives a uniform sample.
+pub fn nth_arrangement(row: &str, runs: &[usize], n: usize) -> Option<String> {
+    return LineTable::new(row.as_bytes(), runs).nth(n);
+}
+
+pub fn arrangements(row: &str, runs: &[usize]) -> Vec<String> {
+    let table = LineTable::new(row.as_bytes(), runs);
+    return (0..table.count()).map(|n| table.nth(n).unwrap()).collect_vec();
+}
+
+/// Fixes every `?` that has the same value in all arrangements; `None` if there are none.
+pub fn solve_line(row: &str, runs: &[usize]) -> Option<String> {
+    let table = LineTable::new(row.as_bytes(), runs);
+    if table.count() == 0 {
+        return None;
+    }
+
+    return Some(
+        table.possible_cells()
+            .into_iter()
+            .map(|cell| match cell {
+                (true, false) => '#',
+                (false, true) => '.',
+                _ => '?'
+            })
+            .collect()
+    );
+}
+
+/// Solves a 2D nonogram by line solving rows and columns until nothing changes, then
+/// guessing on the first open cell.
+pub fn solve_nonogram(row_runs: &[Vec<usize>], col_runs: &[Vec<usize>]) -> Option<Grid> {
+    let cells = vec![vec!['?'; col_runs.len()]; row_runs.len()];
+    let solved = solve_cells(cells, row_runs, col_runs)?;
+
+    return Some(Grid::from_lines(&solved.iter().map(|r| r.iter().join("")).join("\n")));
+}
+
+fn solve_cells(mut cells: Vec<Vec<char>>, row_runs: &[Vec<usize>], col_runs: &[Vec<usize>]) -> Option<Vec<Vec<char>>> {
+    loop {
+        let mut changed = false;
+
+        for (y, runs) in row_runs.iter().enumerate() {
+            let before = cells[y].iter().collect::<String>();
+            let after = solve_line(&before, runs)?;
+            if after != before {
+                cells[y] = after.chars().collect_vec();
+                changed = true;
+            }
+        }
+
+        for (x, runs) in col_runs.iter().enumerate() {
+            let before = cells.iter().map(|r| r[x]).collect::<String>();
+            let after = solve_line(&before, runs)?;
+            if after != before {
+                for (y, c) in after.chars().enumerate() {
+                    cells[y][x] = c;
+                }
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let open = cells
+        .iter()
+        .enumerate()
+        .find_map(|(y, r)| r.iter().position(|c| *c == '?').map(|x| (x, y)));
+
+    return match open {
+        None => Some(cells),
+        Some((x, y)) => ['#', '.']
+            .into_iter()
+            .find_map(|guess| {
+                let mut attempt = cells.clone();
+                attempt[y][x] = guess;
+                solve_cells(attempt, row_runs, col_runs)
+            })
+    };
+}
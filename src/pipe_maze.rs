@@ -0,0 +1,163 @@
+use itertools::Itertools;
+
+use crate::grid::{DIR, Grid, Point};
+use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+use crate::polygon::Polygon;
+
+const TILES: [char; 6] = ['|', '-', 'L', 'J', '7', 'F'];
+
+fn connections(tile: char) -> Vec<DIR> {
+    match tile {
+        '|' => vec![NORTH, SOUTH],
+        '-' => vec![EAST, WEST],
+        'L' => vec![NORTH, EAST],
+        'J' => vec![NORTH, WEST],
+        '7' => vec![SOUTH, WEST],
+        'F' => vec![SOUTH, EAST],
+        _ => vec![]
+    }
+}
+
+fn opposite(dir: &DIR) -> DIR {
+    match dir {
+        NORTH => SOUTH,
+        SOUTH => NORTH,
+        EAST => WEST,
+        WEST => EAST
+    }
+}
+
+/// A pipe map with a single loop through `start`, whose real tile is worked out from
+/// the neighbours that point back at it.
+pub struct PipeMaze {
+    grid: Grid,
+    start: Point,
+    start_tile: char,
+}
+
+impl PipeMaze {
+    pub fn from_lines(s: &str) -> Option<PipeMaze> {
+        let grid = Grid::from_lines(s);
+        let start = grid.find_first('S')?;
+
+        return PipeMaze::with_start(grid, start);
+    }
+
+    pub fn with_start(grid: Grid, start: Point) -> Option<PipeMaze> {
+        let linked = [NORTH, SOUTH, EAST, WEST]
+            .into_iter()
+            .filter(|d| {
+                grid.try_move(&start, d)
+                    .and_then(|n| grid.get_point(&n))
+                    .map(|c| connections(*c).contains(&opposite(d)))
+                    .unwrap_or(false)
+            })
+            .collect_vec();
+
+        // more than one tile can fit when extra neighbours point at the start, so keep
+        // the one whose walk comes back round
+        let candidates = TILES
+            .into_iter()
+            .filter(|t| connections(*t).iter().all(|d| linked.contains(d)))
+            .collect_vec();
+
+        let mut maze = PipeMaze {
+            grid,
+            start,
+            start_tile: ' ',
+        };
+        for t in candidates {
+            maze.start_tile = t;
+            if maze.walk_loop().is_some() {
+                return Some(maze);
+            }
+        }
+
+        return None;
+    }
+
+    pub fn start_tile(&self) -> char {
+        return self.start_tile;
+    }
+
+    pub fn tile(&self, p: &Point) -> char {
+        if *p == self.start {
+            return self.start_tile;
+        }
+
+        return *self.grid.get_point(p).unwrap();
+    }
+
+    /// Loop tiles in walking order, beginning at the start tile.
+    pub fn find_loop(&self) -> Vec<Point> {
+        return self.walk_loop().unwrap();
+    }
+
+    /// Follows the pipes out of the start tile, or `None` if they don't lead back into it.
+    fn walk_loop(&self) -> Option<Vec<Point>> {
+        let mut path = vec![self.start];
+        let mut dir = connections(self.start_tile)[0];
+        let mut current = self.grid.try_move(&self.start, &dir)?;
+
+        loop {
+            let came_from = opposite(&dir);
+            let arms = connections(self.tile(&current));
+            if !arms.contains(&came_from) {
+                return None;
+            }
+            if current == self.start {
+                return Some(path);
+            }
+
+            path.push(current);
+            dir = arms.into_iter().find(|d| *d != came_from)?;
+            current = self.grid.try_move(&current, &dir)?;
+        }
+    }
+
+    pub fn loop_polygon(&self) -> Polygon {
+        return Polygon::new(
+            self.find_loop()
+                .into_iter()
+                .filter(|p| !matches!(self.tile(p), '|' | '-'))
+                .map(|p| (p.x as i64, p.y as i64))
+                .collect_vec()
+        );
+    }
+
+    /// Scanline parity: walking a row, only pipes with a north arm flip inside/outside,
+    /// so `L-7` counts once and `L-J` not at all.
+    pub fn count_enclosed(&self) -> usize {
+        let on_loop = self.loop_mask();
+        let mut count = 0;
+
+        for (y, row) in on_loop.iter().enumerate() {
+            let mut inside = false;
+            for (x, on) in row.iter().enumerate() {
+                if *on {
+                    if connections(self.tile(&Point::new(x as i32, y as i32))).contains(&NORTH) {
+                        inside = !inside;
+                    }
+                } else if inside {
+                    count += 1;
+                }
+            }
+        }
+
+        return count;
+    }
+
+    /// Same answer as `count_enclosed`, via shoelace area and Pick's theorem.
+    pub fn count_enclosed_shoelace(&self) -> usize {
+        return self.loop_polygon().interior_points() as usize;
+    }
+
+    fn loop_mask(&self) -> Vec<Vec<bool>> {
+        let mut mask = vec![vec![false; self.grid.x_size]; self.grid.y_size];
+        for p in self.find_loop() {
+            mask[p.y][p.x] = true;
+        }
+
+        return mask;
+    }
+}
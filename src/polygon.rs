@@ -0,0 +1,161 @@
+use itertools::Itertools;
+use num::integer::gcd;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+    Degenerate,
+}
+
+/// A simple polygon on integer coordinates. The closing edge back to the first vertex
+/// is implied; a trailing copy of the first vertex is dropped.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    vertices: Vec<(i64, i64)>,
+}
+
+impl Polygon {
+    pub fn new(mut vertices: Vec<(i64, i64)>) -> Polygon {
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+
+        return Polygon { vertices };
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item=((i64, i64), (i64, i64))> + '_ {
+        return self.vertices.iter().cloned().circular_tuple_windows();
+    }
+
+    /// Shoelace sum; positive when the vertices run counter-clockwise with y pointing up.
+    pub fn double_signed_area(&self) -> i64 {
+        return self.edges()
+            .map(|((x1, y1), (x2, y2))| (x1 * y2) - (y1 * x2))
+            .sum();
+    }
+
+    pub fn double_area(&self) -> i64 {
+        return self.double_signed_area().abs();
+    }
+
+    /// Rounded down for polygons whose area is a half-integer.
+    pub fn area(&self) -> i64 {
+        return self.double_area() / 2;
+    }
+
+    /// Grid coordinates usually have y growing downward, which flips the visual sense.
+    pub fn orientation(&self) -> Orientation {
+        let a = self.double_signed_area();
+        if a > 0 {
+            return Orientation::CounterClockwise;
+        }
+        if a < 0 {
+            return Orientation::Clockwise;
+        }
+
+        return Orientation::Degenerate;
+    }
+
+    /// Manhattan length of the boundary, which is the true length for rectilinear polygons.
+    pub fn perimeter(&self) -> i64 {
+        return self.edges()
+            .map(|((x1, y1), (x2, y2))| (x2 - x1).abs() + (y2 - y1).abs())
+            .sum();
+    }
+
+    pub fn boundary_points(&self) -> i64 {
+        return self.edges()
+            .map(|((x1, y1), (x2, y2))| gcd(x2 - x1, y2 - y1))
+            .sum();
+    }
+
+    /// Pick's theorem: A = I + B/2 - 1.
+    pub fn interior_points(&self) -> i64 {
+        return (self.double_area() - self.boundary_points() + 2) / 2;
+    }
+
+    pub fn lattice_points(&self) -> i64 {
+        return self.interior_points() + self.boundary_points();
+    }
+
+    pub fn on_boundary(&self, p: (i64, i64)) -> bool {
+        return self.edges().any(|(a, b)| on_segment(a, b, p));
+    }
+
+    /// Strictly inside, by ray casting to the right.
+    pub fn contains(&self, p: (i64, i64)) -> bool {
+        if self.on_boundary(p) {
+            return false;
+        }
+
+        let (px, py) = p;
+        let mut inside = false;
+
+        for ((x1, y1), (x2, y2)) in self.edges() {
+            if (y1 > py) != (y2 > py) {
+                // x of the crossing is x1 + (py - y1) * (x2 - x1) / (y2 - y1); compare without dividing
+                let lhs = (px - x1) * (y2 - y1);
+                let rhs = (py - y1) * (x2 - x1);
+                if (y2 > y1 && lhs < rhs) || (y2 < y1 && lhs > rhs) {
+                    inside = !inside;
+                }
+            }
+        }
+
+        return inside;
+    }
+
+    /// True if any two edges touch other than neighbours meeting at their shared vertex.
+    pub fn is_self_intersecting(&self) -> bool {
+        let edges = self.edges().collect_vec();
+        let n = edges.len();
+
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let (a, b) = edges[i];
+                let (c, d) = edges[j];
+
+                let adjacent = j == i + 1 || (i == 0 && j == n - 1);
+                if adjacent {
+                    // neighbours only clash when the second doubles back over the first
+                    let (shared, p, q) = if j == i + 1 { (b, a, d) } else { (a, b, c) };
+                    if cross(shared, p, q) == 0 && dot(shared, p, q) > 0 {
+                        return true;
+                    }
+                } else if segments_touch(a, b, c, d) {
+                    return true;
+                }
+            }
+        }
+
+        return false;
+    }
+}
+
+fn cross(o: (i64, i64), a: (i64, i64), b: (i64, i64)) -> i64 {
+    return (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+}
+
+fn dot(o: (i64, i64), a: (i64, i64), b: (i64, i64)) -> i64 {
+    return (a.0 - o.0) * (b.0 - o.0) + (a.1 - o.1) * (b.1 - o.1);
+}
+
+fn on_segment(a: (i64, i64), b: (i64, i64), p: (i64, i64)) -> bool {
+    return cross(a, b, p) == 0
+        && p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0)
+        && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1);
+}
+
+fn segments_touch(a: (i64, i64), b: (i64, i64), c: (i64, i64), d: (i64, i64)) -> bool {
+    let d1 = cross(c, d, a).signum();
+    let d2 = cross(c, d, b).signum();
+    let d3 = cross(a, b, c).signum();
+    let d4 = cross(a, b, d).signum();
+
+    if d1 * d2 < 0 && d3 * d4 < 0 {
+        return true;
+    }
+
+    return on_segment(c, d, a) || on_segment(c, d, b) || on_segment(a, b, c) || on_segment(a, b, d);
+}
@@ -0,0 +1,248 @@
+use std::cmp;
+use std::ops::Range;
+
+use itertools::Itertools;
+use num::PrimInt;
+
+/// A set of integers stored as sorted, disjoint, non-adjacent half-open ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeSet<T: PrimInt> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: PrimInt> RangeSet<T> {
+    pub fn new() -> RangeSet<T> {
+        return RangeSet { ranges: vec![] };
+    }
+
+    pub fn from_ranges<I: IntoIterator<Item=Range<T>>>(ranges: I) -> RangeSet<T> {
+        let sorted = ranges
+            .into_iter()
+            .filter(|r| r.start < r.end)
+            .sorted_by_key(|r| r.start)
+            .collect_vec();
+
+        let mut coalesced: Vec<Range<T>> = vec![];
+        for r in sorted {
+            match coalesced.last_mut() {
+                Some(last) if r.start <= last.end => last.end = cmp::max(last.end, r.end),
+                _ => coalesced.push(r)
+            }
+        }
+
+        return RangeSet { ranges: coalesced };
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        return &self.ranges;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.ranges.is_empty();
+    }
+
+    /// Number of integers in the set.
+    pub fn count(&self) -> T {
+        return self.ranges.iter().fold(T::zero(), |acc, r| acc + (r.end - r.start));
+    }
+
+    pub fn min(&self) -> Option<T> {
+        return self.ranges.first().map(|r| r.start);
+    }
+
+    pub fn contains(&self, v: T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.end <= v);
+        return self.ranges.get(idx).map(|r| r.contains(&v)).unwrap_or(false);
+    }
+
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        return RangeSet::from_ranges(self.ranges.iter().chain(other.ranges.iter()).cloned());
+    }
+
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut result = vec![];
+        let (mut i, mut j) = (0, 0);
+
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let a = &self.ranges[i];
+            let b = &other.ranges[j];
+
+            let start = cmp::max(a.start, b.start);
+            let end = cmp::min(a.end, b.end);
+            if start < end {
+                result.push(start..end);
+            }
+
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        return RangeSet::from_ranges(result);
+    }
+
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut result = vec![];
+        let mut j = 0;
+
+        for a in &self.ranges {
+            let mut start = a.start;
+
+            while j < other.ranges.len() && other.ranges[j].end <= start {
+                j += 1;
+            }
+
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < a.end {
+                let b = &other.ranges[k];
+                if b.start > start {
+                    result.push(start..b.start);
+                }
+                start = cmp::max(start, b.end);
+                k += 1;
+            }
+
+            if start < a.end {
+                result.push(start..a.end);
+            }
+        }
+
+        return RangeSet::from_ranges(result);
+    }
+
+    /// Moves every range by `delta`, which may be negative for unsigned `T`.
+    pub fn shift(&self, delta: i128) -> RangeSet<T> {
+        return RangeSet::from_ranges(self.ranges.iter().map(|r| shift_range(r, delta)));
+    }
+}
+
+fn shift_value<T: PrimInt>(v: T, delta: i128) -> T {
+    return T::from(v.to_i128().unwrap() + delta).unwrap();
+}
+
+fn shift_range<T: PrimInt>(r: &Range<T>, delta: i128) -> Range<T> {
+    return shift_value(r.start, delta)..shift_value(r.end, delta);
+}
+
+/// A piecewise-linear function on integers: each source range is translated by its
+/// own offset and everything outside the listed ranges maps to itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeMap<T: PrimInt> {
+    pieces: Vec<(Range<T>, i128)>,
+}
+
+impl<T: PrimInt> RangeMap<T> {
+    pub fn new() -> RangeMap<T> {
+        return RangeMap { pieces: vec![] };
+    }
+
+    /// Maps `source` onto `dest_start..`. Source ranges must not overlap earlier inserts.
+    pub fn insert(&mut self, source: Range<T>, dest_start: T) {
+        let offset = dest_start.to_i128().unwrap() - source.start.to_i128().unwrap();
+        let idx = self.pieces.partition_point(|(r, _)| r.start < source.start);
+        self.pieces.insert(idx, (source, offset));
+    }
+
+    pub fn map(&self, v: T) -> T {
+        let idx = self.pieces.partition_point(|(r, _)| r.end <= v);
+        return match self.pieces.get(idx) {
+            Some((r, offset)) if r.contains(&v) => shift_value(v, *offset),
+            _ => v
+        };
+    }
+
+    /// Every segment of the domain with its offset, including the identity gaps.
+    pub fn segments(&self) -> Vec<(Range<T>, i128)> {
+        let mut result = vec![];
+        let mut cursor = T::min_value();
+
+        for (r, offset) in &self.pieces {
+            if cursor < r.start {
+                result.push((cursor..r.start, 0));
+            }
+            result.push((r.clone(), *offset));
+            cursor = r.end;
+        }
+
+        if cursor < T::max_value() {
+            result.push((cursor..T::max_value(), 0));
+        }
+
+        return result;
+    }
+
+    pub fn map_set(&self, input: &RangeSet<T>) -> RangeSet<T> {
+        let mut result = vec![];
+
+        for (r, offset) in self.segments() {
+            let overlap = input.intersection(&RangeSet::from_ranges([r]));
+            result.extend(overlap.ranges().iter().map(|o| shift_range(o, offset)));
+        }
+
+        return RangeSet::from_ranges(result);
+    }
+
+    /// `self` followed by `next`, as a single map.
+    pub fn compose(&self, next: &RangeMap<T>) -> RangeMap<T> {
+        let next_segments = next.segments();
+        let mut pieces: Vec<(Range<T>, i128)> = vec![];
+
+        for (r, offset) in self.segments() {
+            let image = shift_range(&r, offset);
+
+            for (nr, next_offset) in &next_segments {
+                let start = cmp::max(image.start, nr.start);
+                let end = cmp::min(image.end, nr.end);
+                if start >= end {
+                    continue;
+                }
+
+                let source = shift_value(start, -offset)..shift_value(end, -offset);
+                let total = offset + next_offset;
+
+                match pieces.last_mut() {
+                    Some((last, last_offset)) if last.end == source.start && *last_offset == total => last.end = source.end,
+                    _ => pieces.push((source, total))
+                }
+            }
+        }
+
+        pieces.sort_by_key(|(r, _)| r.start);
+        pieces.retain(|(_, offset)| *offset != 0);
+
+        return RangeMap { pieces };
+    }
+
+    /// Smallest value `input` maps to. Each segment is increasing, so only the first
+    /// covered value of every segment needs checking.
+    pub fn min_image(&self, input: &RangeSet<T>) -> Option<T> {
+        let mut best = None;
+
+        for (r, offset) in self.segments() {
+            for i in input.ranges() {
+                let start = cmp::max(r.start, i.start);
+                if start < cmp::min(r.end, i.end) {
+                    let v = shift_value(start, offset);
+                    best = Some(best.map_or(v, |b: T| cmp::min(b, v)));
+                }
+            }
+        }
+
+        return best;
+    }
+
+    /// Every value that maps into `target`.
+    pub fn preimage(&self, target: &RangeSet<T>) -> RangeSet<T> {
+        let mut result = vec![];
+
+        for (r, offset) in self.segments() {
+            let image = RangeSet::from_ranges([shift_range(&r, offset)]);
+            let hit = image.intersection(target);
+            result.extend(hit.ranges().iter().map(|h| shift_range(h, -offset)));
+        }
+
+        return RangeSet::from_ranges(result);
+    }
+}
@@ -0,0 +1,105 @@
+use itertools::Itertools;
+
+use crate::grid::{Grid, Point};
+use crate::grid_mask::{BitRow, GridMask};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Axis {
+    // mirror line between two columns
+    Vertical,
+    // mirror line between two rows
+    Horizontal,
+}
+
+/// A candidate mirror line `position` cells from the left/top edge, with every pair of
+/// mirrored cells that disagree. Flipping either cell of a pair fixes that smudge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reflection {
+    pub axis: Axis,
+    pub position: usize,
+    pub smudges: Vec<(Point, Point)>,
+}
+
+impl Reflection {
+    /// The puzzle's note value: columns left of a vertical line, 100x rows above a horizontal one.
+    pub fn summary(&self) -> usize {
+        return match self.axis {
+            Axis::Vertical => self.position,
+            Axis::Horizontal => self.position * 100,
+        };
+    }
+}
+
+/// Every possible mirror line of the grid, each compared across its full overlap once.
+pub fn reflections(grid: &Grid) -> Vec<Reflection> {
+    return (1..grid.x_size)
+        .map(|position| vertical_reflection(grid, position))
+        .chain((1..grid.y_size).map(|position| horizontal_reflection(grid, position)))
+        .collect_vec();
+}
+
+/// Mirror lines with exactly `smudges` mismatched pairs. Candidates are counted on row and
+/// column bitmasks first, so smudge locations are only collected for the lines that match.
+pub fn reflections_with_smudges(grid: &Grid, smudges: usize) -> Vec<Reflection> {
+    let masks = grid.print_string()
+        .chars()
+        .filter(|c| *c != '\n')
+        .unique()
+        .map(|c| GridMask::from_grid(grid, c))
+        .collect_vec();
+
+    // a mismatched cell pair differs in the masks of both of its symbols
+    let mismatches = |lines: fn(&GridMask) -> &Vec<BitRow>, position: usize| -> usize {
+        let n = lines(&masks[0]).len();
+        let span = position.min(n - position);
+        let total: u32 = masks
+            .iter()
+            .map(|m| {
+                let l = lines(m);
+                (0..span).map(|d| l[position - 1 - d].hamming(&l[position + d])).sum::<u32>()
+            })
+            .sum();
+        return total as usize / 2;
+    };
+
+    let vertical = (1..grid.x_size)
+        .filter(|p| mismatches(|m| &m.cols, *p) == smudges)
+        .map(|p| vertical_reflection(grid, p));
+    let horizontal = (1..grid.y_size)
+        .filter(|p| mismatches(|m| &m.rows, *p) == smudges)
+        .map(|p| horizontal_reflection(grid, p));
+
+    return vertical.chain(horizontal).collect_vec();
+}
+
+fn vertical_reflection(grid: &Grid, position: usize) -> Reflection {
+    let width = position.min(grid.x_size - position);
+    let mut smudges = vec![];
+    for y in 0..grid.y_size {
+        for d in 0..width {
+            let a = Point::new((position - 1 - d) as i32, y as i32);
+            let b = Point::new((position + d) as i32, y as i32);
+            if grid.get_point(&a) != grid.get_point(&b) {
+                smudges.push((a, b));
+            }
+        }
+    }
+
+    return Reflection { axis: Axis::Vertical, position, smudges };
+}
+
+fn horizontal_reflection(grid: &Grid, position: usize) -> Reflection {
+    let height = position.min(grid.y_size - position);
+    let mut smudges = vec![];
+    for d in 0..height {
+        for x in 0..grid.x_size {
+            let a = Point::new(x as i32, (position - 1 - d) as i32);
+            let b = Point::new(x as i32, (position + d) as i32);
+            if grid.get_point(&a) != grid.get_point(&b) {
+                smudges.push((a, b));
+            }
+        }
+    }
+
+    return Reflection { axis: Axis::Horizontal, position, smudges };
+}
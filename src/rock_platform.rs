@@ -0,0 +1,168 @@
+use itertools::Itertools;
+
+use crate::cycle::find_cycle;
+use crate::grid::{DIR, Grid, Point};
+use crate::grid::DIR::{EAST, NORTH, SOUTH, WEST};
+
+const SPIN: [DIR; 4] = [NORTH, WEST, SOUTH, EAST];
+
+/// A platform of round rocks (`O`) that roll and cube rocks (`#`) that don't. Rocks are
+/// kept as coordinate lists; the round ones stay sorted so equal states compare equal.
+#[derive(Debug, Clone)]
+pub struct Platform {
+    width: usize,
+    height: usize,
+    cubes: Vec<Point>,
+    round: Vec<Point>,
+    // per direction, the cell a rock starting on each cell rolls to on an empty platform
+    stops: [Vec<usize>; 4],
+}
+
+impl Platform {
+    pub fn from_lines(s: &str) -> Platform {
+        let grid = Grid::from_lines(s);
+        let mut cubes = vec![];
+        let mut round = vec![];
+
+        for y in 0..grid.y_size {
+            for x in 0..grid.x_size {
+                match grid.get(x, y).unwrap() {
+                    '#' => cubes.push(Point { x, y }),
+                    'O' => round.push(Point { x, y }),
+                    _ => {}
+                }
+            }
+        }
+
+        return Platform::new(grid.x_size, grid.y_size, cubes, round);
+    }
+
+    pub fn new(width: usize, height: usize, cubes: Vec<Point>, mut round: Vec<Point>) -> Platform {
+        let mut blocked = vec![false; width * height];
+        for c in &cubes {
+            blocked[c.y * width + c.x] = true;
+        }
+
+        round.sort();
+
+        return Platform {
+            width,
+            height,
+            cubes,
+            round,
+            stops: DIR::ALL.map(|d| Platform::compute_stops(width, height, &blocked, &d)),
+        };
+    }
+
+    /// Walks each line from the edge rocks roll towards, remembering the last free cell
+    /// after a wall or cube.
+    fn compute_stops(width: usize, height: usize, blocked: &[bool], dir: &DIR) -> Vec<usize> {
+        let mut stops = vec![0; width * height];
+        let (lines, len) = match dir {
+            NORTH | SOUTH => (width, height),
+            EAST | WEST => (height, width),
+        };
+
+        for line in 0..lines {
+            let mut stop = None;
+            for step in 0..len {
+                let along = match dir {
+                    NORTH | WEST => step,
+                    SOUTH | EAST => len - 1 - step,
+                };
+                let idx = match dir {
+                    NORTH | SOUTH => along * width + line,
+                    EAST | WEST => line * width + along,
+                };
+
+                if blocked[idx] {
+                    stop = None;
+                } else {
+                    let s = *stop.get_or_insert(idx);
+                    stops[idx] = s;
+                }
+            }
+        }
+
+        return stops;
+    }
+
+    pub fn round_rocks(&self) -> &Vec<Point> {
+        return &self.round;
+    }
+
+    pub fn tilt(&mut self, dir: &DIR) {
+        self.round = self.tilted(&self.round, dir);
+    }
+
+    /// North, west, south, then east.
+    pub fn spin_cycle(&mut self) {
+        self.round = self.spun(&self.round);
+    }
+
+    /// Each round rock weighs its distance from the edge opposite `dir`, counting its own row.
+    pub fn load(&self, dir: &DIR) -> usize {
+        return self.round
+            .iter()
+            .map(|p| match dir {
+                NORTH => self.height - p.y,
+                SOUTH => p.y + 1,
+                WEST => self.width - p.x,
+                EAST => p.x + 1,
+            })
+            .sum();
+    }
+
+    /// The platform after `n` spin cycles, jumping ahead once the rock positions repeat.
+    pub fn state_after(&self, n: usize) -> Platform {
+        let cycle = find_cycle(self.round.clone(), |r| self.spun(r));
+
+        let mut result = self.clone();
+        result.round = cycle.state_at(n).clone();
+        return result;
+    }
+
+    pub fn print_string(&self) -> String {
+        let mut rows = vec![vec!['.'; self.width]; self.height];
+        for c in &self.cubes {
+            rows[c.y][c.x] = '#';
+        }
+        for r in &self.round {
+            rows[r.y][r.x] = 'O';
+        }
+
+        return rows.iter().map(|r| r.iter().join("")).join("\n");
+    }
+
+    fn spun(&self, round: &[Point]) -> Vec<Point> {
+        return SPIN
+            .iter()
+            .fold(round.to_vec(), |rocks, d| self.tilted(&rocks, d));
+    }
+
+    /// Rocks sharing a stop pile up from it, one cell further back per rock.
+    fn tilted(&self, round: &[Point], dir: &DIR) -> Vec<Point> {
+        let stops = &self.stops[dir.index()];
+        let mut piled = vec![0; self.width * self.height];
+
+        let mut result = round
+            .iter()
+            .map(|p| {
+                let stop = stops[p.y * self.width + p.x];
+                let depth = piled[stop];
+                piled[stop] += 1;
+
+                let (x, y) = (stop % self.width, stop / self.width);
+                return match dir {
+                    NORTH => Point { x, y: y + depth },
+                    SOUTH => Point { x, y: y - depth },
+                    WEST => Point { x: x + depth, y },
+                    EAST => Point { x: x - depth, y },
+                };
+            })
+            .collect_vec();
+
+        result.sort();
+        return result;
+    }
+}
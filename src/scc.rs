@@ -0,0 +1,66 @@
+/// Tarjan's algorithm over an adjacency list. Components come out in reverse
+/// topological order: every component is listed after all the ones it can reach.
+pub fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut tarjan = Tarjan::new(edges.len());
+    for v in 0..edges.len() {
+        if tarjan.order[v].is_none() {
+            tarjan.visit(v, edges);
+        }
+    }
+
+    return tarjan.components;
+}
+
+struct Tarjan {
+    order: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    counter: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan {
+    fn new(n: usize) -> Tarjan {
+        return Tarjan {
+            order: vec![None; n],
+            low: vec![0; n],
+            on_stack: vec![false; n],
+            stack: vec![],
+            counter: 0,
+            components: vec![],
+        };
+    }
+
+    fn visit(&mut self, v: usize, edges: &[Vec<usize>]) {
+        self.order[v] = Some(self.counter);
+        self.low[v] = self.counter;
+        self.counter += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+
+        for &w in &edges[v] {
+            match self.order[w] {
+                None => {
+                    self.visit(w, edges);
+                    self.low[v] = self.low[v].min(self.low[w]);
+                }
+                Some(o) if self.on_stack[w] => self.low[v] = self.low[v].min(o),
+                _ => {}
+            }
+        }
+
+        if Some(self.low[v]) == self.order[v] {
+            let mut component = vec![];
+            loop {
+                let w = self.stack.pop().unwrap();
+                self.on_stack[w] = false;
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::circuit::{BUTTON, CircuitError, Event, Machine, PV};
+
+/// Which modules get a signal in the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceFilter {
+    All,
+    Modules(Vec<String>),
+    Kinds(Vec<&'static str>),
+}
+
+/// Records the level of the last pulse each traced module sent, one time step per pulse,
+/// plus a `press` counter that changes when a button press starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecorder {
+    // module index, name and VCD identifier, in machine order
+    signals: Vec<(usize, String, String)>,
+    press_id: String,
+    level: HashMap<usize, PV>,
+    time: u64,
+    press: usize,
+    changes: Vec<(u64, String)>,
+}
+
+/// Short printable identifiers: `!`, `"`, ..., `~`, then two characters and so on.
+fn identifier(mut n: usize) -> String {
+    let mut id = String::new();
+    loop {
+        id.push((b'!' + (n % 94) as u8) as char);
+        n /= 94;
+        if n == 0 {
+            return id;
+        }
+        n -= 1;
+    }
+}
+
+impl TraceRecorder {
+    pub fn new(machine: &Machine, filter: &TraceFilter) -> Result<TraceRecorder, CircuitError> {
+        let modules = match filter {
+            TraceFilter::All => (0..machine.len()).collect_vec(),
+            TraceFilter::Modules(names) => names
+                .iter()
+                .map(|n| machine.index(n).ok_or(CircuitError::UnknownModule { name: n.clone() }))
+                .collect::<Result<Vec<usize>, CircuitError>>()?
+                .into_iter()
+                .sorted()
+                .dedup()
+                .collect_vec(),
+            TraceFilter::Kinds(kinds) => (0..machine.len()).filter(|m| kinds.contains(&machine.kind(*m))).collect_vec(),
+        };
+
+        return Ok(TraceRecorder {
+            signals: modules
+                .iter()
+                .enumerate()
+                .map(|(i, m)| (*m, machine.name(*m).to_owned(), identifier(i + 1)))
+                .collect_vec(),
+            press_id: identifier(0),
+            level: HashMap::new(),
+            time: 0,
+            press: 0,
+            changes: vec![],
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.level.clear();
+        self.time = 0;
+        self.press = 0;
+        self.changes.clear();
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        self.time += 1;
+        if event.press != self.press {
+            self.press = event.press;
+            self.changes.push((self.time, format!("b{:b} {}", self.press, self.press_id)));
+        }
+        if event.from == BUTTON {
+            return;
+        }
+
+        let Some((_, _, id)) = self.signals.iter().find(|(m, _, _)| *m == event.from) else {
+            return;
+        };
+        let previous = self.level.insert(event.from, event.value).unwrap_or(PV::LOW);
+        if previous != event.value {
+            let bit = if event.value == PV::HIGH { '1' } else { '0' };
+            self.changes.push((self.time, format!("{bit}{id}")));
+        }
+    }
+
+    pub fn to_vcd(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "$version day 20 pulse trace $end").unwrap();
+        writeln!(out, "$timescale 1ns $end").unwrap();
+        writeln!(out, "$scope module circuit $end").unwrap();
+        writeln!(out, "$var integer 32 {} press $end", self.press_id).unwrap();
+        for (_, name, id) in &self.signals {
+            writeln!(out, "$var wire 1 {id} {name} $end").unwrap();
+        }
+        writeln!(out, "$upscope $end").unwrap();
+        writeln!(out, "$enddefinitions $end").unwrap();
+
+        writeln!(out, "#0").unwrap();
+        writeln!(out, "$dumpvars").unwrap();
+        writeln!(out, "b0 {}", self.press_id).unwrap();
+        for (_, _, id) in &self.signals {
+            writeln!(out, "0{id}").unwrap();
+        }
+        writeln!(out, "$end").unwrap();
+
+        for (time, changes) in &self.changes.iter().group_by(|(t, _)| *t) {
+            writeln!(out, "#{time}").unwrap();
+            for (_, c) in changes {
+                writeln!(out, "{c}").unwrap();
+            }
+        }
+
+        return out;
+    }
+
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        return std::fs::write(path, self.to_vcd());
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub enum Attribute {
+    X,
+    M,
+    A,
+    S,
+    // anything outside the puzzle's four ratings
+    Named(String),
+}
+
+impl Attribute {
+    pub const XMAS: [Attribute; 4] = [Attribute::X, Attribute::M, Attribute::A, Attribute::S];
+
+    pub fn from_name(name: &str) -> Attribute {
+        return match name {
+            "x" => Attribute::X,
+            "m" => Attribute::M,
+            "a" => Attribute::A,
+            "s" => Attribute::S,
+            _ => Attribute::Named(name.to_owned())
+        };
+    }
+
+    pub fn name(&self) -> &str {
+        return match self {
+            Attribute::X => "x",
+            Attribute::M => "m",
+            Attribute::A => "a",
+            Attribute::S => "s",
+            Attribute::Named(name) => name,
+        };
+    }
+}
+
+/// Inclusive `(lo, hi)` range of ratings.
+pub type Interval = (i64, i64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Equal,
+}
+
+impl Comparison {
+    pub fn test(&self, rating: i64, value: i64) -> bool {
+        return match self {
+            Comparison::Less => rating < value,
+            Comparison::LessEq => rating <= value,
+            Comparison::Greater => rating > value,
+            Comparison::GreaterEq => rating >= value,
+            Comparison::Equal => rating == value,
+        };
+    }
+
+    /// Splits the inclusive range `(lo, hi)` into the part that passes and the parts
+    /// that don't. Only `==` can leave a gap in the middle, so there are at most two.
+    pub fn split(&self, (lo, hi): Interval, value: i64) -> (Option<Interval>, Vec<Interval>) {
+        let (pass_lo, pass_hi) = match self {
+            Comparison::Less => (lo, hi.min(value - 1)),
+            Comparison::LessEq => (lo, hi.min(value)),
+            Comparison::Greater => (lo.max(value + 1), hi),
+            Comparison::GreaterEq => (lo.max(value), hi),
+            Comparison::Equal => (lo.max(value), hi.min(value)),
+        };
+
+        if pass_lo > pass_hi {
+            return (None, vec![(lo, hi)]);
+        }
+
+        let fail = [(lo, pass_lo - 1), (pass_hi + 1, hi)]
+            .into_iter()
+            .filter(|(a, b)| a <= b)
+            .collect_vec();
+
+        return (Some((pass_lo, pass_hi)), fail);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub attribute: Attribute,
+    pub comparison: Comparison,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+    Accept,
+    Reject,
+    Workflow(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    // `None` always matches
+    pub condition: Option<Condition>,
+    pub target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workflow {
+    pub name: String,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub ratings: HashMap<Attribute, i64>,
+}
+
+impl Part {
+    pub fn total(&self) -> i64 {
+        return self.ratings.values().sum();
+    }
+}
+
+/// Workflows in file order, looked up by name.
+#[derive(Debug, Clone)]
+pub struct WorkflowSet {
+    pub workflows: Vec<Workflow>,
+    index: HashMap<String, usize>,
+}
+
+impl WorkflowSet {
+    pub fn new(workflows: Vec<Workflow>) -> WorkflowSet {
+        let index = workflows
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.name.clone(), i))
+            .collect();
+
+        return WorkflowSet { workflows, index };
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Workflow> {
+        return self.index.get(name).map(|i| &self.workflows[*i]);
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        return self.index.get(name).copied();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Number(i64),
+    Compare(Comparison),
+    Assign,
+    Colon,
+    Comma,
+    Open,
+    Close,
+    Newline,
+    End,
+}
+
+struct Lexeme {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Lexeme>, ParseError> {
+    let mut lexemes = vec![];
+
+    for (l, text) in input.lines().enumerate() {
+        let chars = text.chars().collect_vec();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let column = i + 1;
+            let next = chars.get(i + 1).copied();
+            let (token, len) = match chars[i] {
+                c if c.is_whitespace() => {
+                    i += 1;
+                    continue;
+                }
+                c if c.is_ascii_alphabetic() => {
+                    let len = chars[i..].iter().take_while(|c| c.is_ascii_alphanumeric()).count();
+                    (Token::Ident(chars[i..i + len].iter().collect()), len)
+                }
+                c if c.is_ascii_digit() => {
+                    let len = chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+                    let digits = chars[i..i + len].iter().collect::<String>();
+                    let value = digits.parse::<i64>().map_err(|_| ParseError {
+                        line: l + 1,
+                        column,
+                        message: format!("number {digits} is too large"),
+                    })?;
+                    (Token::Number(value), len)
+                }
+                '<' if next == Some('=') => (Token::Compare(Comparison::LessEq), 2),
+                '>' if next == Some('=') => (Token::Compare(Comparison::GreaterEq), 2),
+                '=' if next == Some('=') => (Token::Compare(Comparison::Equal), 2),
+                '<' => (Token::Compare(Comparison::Less), 1),
+                '>' => (Token::Compare(Comparison::Greater), 1),
+                '=' => (Token::Assign, 1),
+                ':' => (Token::Colon, 1),
+                ',' => (Token::Comma, 1),
+                '{' => (Token::Open, 1),
+                '}' => (Token::Close, 1),
+                c => return Err(ParseError { line: l + 1, column, message: format!("unexpected character '{c}'") }),
+            };
+
+            lexemes.push(Lexeme { token, line: l + 1, column });
+            i += len;
+        }
+
+        lexemes.push(Lexeme { token: Token::Newline, line: l + 1, column: chars.len() + 1 });
+    }
+
+    let line = lexemes.last().map(|l| l.line).unwrap_or(1);
+    lexemes.push(Lexeme { token: Token::End, line, column: 1 });
+
+    return Ok(lexemes);
+}
+
+struct Parser {
+    lexemes: Vec<Lexeme>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        return &self.lexemes[self.pos].token;
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        let at = &self.lexemes[self.pos];
+        return ParseError { line: at.line, column: at.column, message: message.to_owned() };
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), ParseError> {
+        if *self.peek() != token {
+            return Err(self.error(&format!("expected {what}")));
+        }
+
+        self.pos += 1;
+        return Ok(());
+    }
+
+    fn ident(&mut self, what: &str) -> Result<String, ParseError> {
+        if let Token::Ident(name) = self.peek() {
+            let name = name.clone();
+            self.pos += 1;
+            return Ok(name);
+        }
+
+        return Err(self.error(&format!("expected {what}")));
+    }
+
+    fn number(&mut self) -> Result<i64, ParseError> {
+        if let Token::Number(value) = self.peek() {
+            let value = *value;
+            self.pos += 1;
+            return Ok(value);
+        }
+
+        return Err(self.error("expected a number"));
+    }
+
+    fn attribute(&mut self) -> Result<Attribute, ParseError> {
+        return Ok(Attribute::from_name(&self.ident("an attribute")?));
+    }
+
+    fn skip_newlines(&mut self) {
+        while *self.peek() == Token::Newline {
+            self.pos += 1;
+        }
+    }
+
+    fn target(name: String) -> Target {
+        return match name.as_str() {
+            "A" => Target::Accept,
+            "R" => Target::Reject,
+            _ => Target::Workflow(name)
+        };
+    }
+
+    fn rule(&mut self) -> Result<Rule, ParseError> {
+        let name = self.ident("a rule")?;
+
+        let Token::Compare(comparison) = *self.peek() else {
+            return Ok(Rule { condition: None, target: Parser::target(name) });
+        };
+
+        let attribute = Attribute::from_name(&name);
+        self.pos += 1;
+        let value = self.number()?;
+        self.expect(Token::Colon, "':' after the comparison")?;
+        let target = Parser::target(self.ident("a destination")?);
+
+        return Ok(Rule {
+            condition: Some(Condition { attribute, comparison, value }),
+            target,
+        });
+    }
+
+    fn workflow(&mut self) -> Result<Workflow, ParseError> {
+        let name = self.ident("a workflow name")?;
+        self.expect(Token::Open, "'{'")?;
+
+        let mut rules = vec![self.rule()?];
+        while *self.peek() == Token::Comma {
+            self.pos += 1;
+            rules.push(self.rule()?);
+        }
+
+        self.expect(Token::Close, "',' or '}'")?;
+        return Ok(Workflow { name, rules });
+    }
+
+    fn part(&mut self) -> Result<Part, ParseError> {
+        self.expect(Token::Open, "'{'")?;
+
+        let mut ratings = HashMap::new();
+        loop {
+            let at = self.pos;
+            let attribute = self.attribute()?;
+            self.expect(Token::Assign, "'='")?;
+            if ratings.insert(attribute, self.number()?).is_some() {
+                self.pos = at;
+                return Err(self.error("attribute rated twice"));
+            }
+
+            if *self.peek() != Token::Comma {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        self.expect(Token::Close, "',' or '}'")?;
+        return Ok(Part { ratings });
+    }
+
+    fn end_of_line(&mut self) -> Result<(), ParseError> {
+        if !matches!(self.peek(), Token::Newline | Token::End) {
+            return Err(self.error("expected end of line"));
+        }
+
+        return Ok(());
+    }
+}
+
+/// Workflows one per line, then optionally a blank line and parts one per line.
+pub fn parse_system(input: &str) -> Result<(WorkflowSet, Vec<Part>), ParseError> {
+    let mut parser = Parser { lexemes: tokenize(input)?, pos: 0 };
+    let mut workflows: Vec<Workflow> = vec![];
+    let mut parts = vec![];
+
+    parser.skip_newlines();
+    while matches!(parser.peek(), Token::Ident(_)) {
+        let at = parser.pos;
+        let workflow = parser.workflow()?;
+        if workflows.iter().any(|w| w.name == workflow.name) {
+            parser.pos = at;
+            return Err(parser.error(&format!("workflow '{}' is defined twice", workflow.name)));
+        }
+
+        workflows.push(workflow);
+        parser.end_of_line()?;
+        parser.skip_newlines();
+    }
+
+    while *parser.peek() == Token::Open {
+        parts.push(parser.part()?);
+        parser.end_of_line()?;
+        parser.skip_newlines();
+    }
+
+    if *parser.peek() != Token::End {
+        return Err(parser.error("expected a workflow or a part"));
+    }
+
+    return Ok((WorkflowSet::new(workflows), parts));
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    Accept,
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    MissingStart { name: String },
+    UndefinedWorkflow { name: String, used_in: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    MissingRating { attribute: Attribute },
+    NoRuleMatched { workflow: String },
+    Cycle { workflow: String },
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Jump {
+    Verdict(Verdict),
+    Workflow(usize),
+}
+
+struct CompiledRule {
+    condition: Option<Condition>,
+    jump: Jump,
+}
+
+/// Workflows with destinations resolved to indexes, so evaluating a part never looks up
+/// a name or compares strings.
+pub struct CompiledWorkflows {
+    names: Vec<String>,
+    rules: Vec<Vec<CompiledRule>>,
+    start: usize,
+}
+
+impl CompiledWorkflows {
+    pub fn compile(set: &WorkflowSet, start: &str) -> Result<CompiledWorkflows, CompileError> {
+        let start = *set.index.get(start).ok_or(CompileError::MissingStart { name: start.to_owned() })?;
+
+        let rules = set.workflows
+            .iter()
+            .map(|w| {
+                w.rules
+                    .iter()
+                    .map(|r| {
+                        let jump = match &r.target {
+                            Target::Accept => Jump::Verdict(Verdict::Accept),
+                            Target::Reject => Jump::Verdict(Verdict::Reject),
+                            Target::Workflow(name) => Jump::Workflow(*set.index.get(name).ok_or(
+                                CompileError::UndefinedWorkflow { name: name.clone(), used_in: w.name.clone() }
+                            )?),
+                        };
+
+                        return Ok(CompiledRule { condition: r.condition.clone(), jump });
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        return Ok(CompiledWorkflows {
+            names: set.workflows.iter().map(|w| w.name.clone()).collect_vec(),
+            rules,
+            start,
+        });
+    }
+
+    pub fn evaluate(&self, part: &Part) -> Result<Verdict, EvalError> {
+        let mut current = self.start;
+
+        // a part can't visit more workflows than exist without going round a cycle
+        for _ in 0..=self.rules.len() {
+            let mut jump = None;
+            for rule in &self.rules[current] {
+                let matched = match &rule.condition {
+                    None => true,
+                    Some(c) => {
+                        let rating = part.ratings.get(&c.attribute).ok_or(EvalError::MissingRating { attribute: c.attribute.clone() })?;
+                        c.comparison.test(*rating, c.value)
+                    }
+                };
+
+                if matched {
+                    jump = Some(rule.jump);
+                    break;
+                }
+            }
+
+            match jump {
+                None => return Err(EvalError::NoRuleMatched { workflow: self.names[current].clone() }),
+                Some(Jump::Verdict(v)) => return Ok(v),
+                Some(Jump::Workflow(next)) => current = next,
+            }
+        }
+
+        return Err(EvalError::Cycle { workflow: self.names[current].clone() });
+    }
+}
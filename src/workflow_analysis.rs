@@ -0,0 +1,221 @@
+use std::collections::{HashSet, VecDeque};
+
+use itertools::Itertools;
+
+use crate::scc::strongly_connected_components;
+use crate::hyperrect::Hyperrect;
+use crate::workflow::{Target, Verdict, Workflow, WorkflowSet};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    MissingStart { name: String },
+    UndefinedTarget { workflow: String, rule: usize, target: String },
+    // workflows that send to each other, though maybe never for the same part
+    GraphCycle { workflows: Vec<String> },
+    // some part goes round these workflows forever, in this order
+    Cycle { workflows: Vec<String> },
+    Unreachable { workflow: String },
+    // an earlier rule in the same workflow already catches every part this one would
+    DeadRule { workflow: String, rule: usize },
+    AlwaysAccepts { workflow: String },
+    AlwaysRejects { workflow: String },
+}
+
+impl Issue {
+    /// Issues that make walking the workflows from the start panic or never finish. A
+    /// `GraphCycle` alone isn't one, as the conditions may keep every part from looping.
+    pub fn is_fatal(&self) -> bool {
+        return matches!(self, Issue::MissingStart { .. } | Issue::UndefinedTarget { .. } | Issue::Cycle { .. });
+    }
+}
+
+/// Which rules of `workflow` can fire for some part inside `domain`, checked against
+/// the boxes the earlier rules let through.
+pub fn live_rules(workflow: &Workflow, domain: &Hyperrect) -> Vec<bool> {
+    let mut remaining = vec![domain.clone()];
+
+    return workflow.rules
+        .iter()
+        .map(|r| {
+            let Some(c) = &r.condition else {
+                let live = !remaining.is_empty();
+                remaining.clear();
+                return live;
+            };
+
+            let mut live = false;
+            let mut failed = vec![];
+            for b in remaining.drain(..) {
+                let (pass, fail) = b.split_by(c);
+                live |= pass.is_some();
+                failed.extend(fail);
+            }
+            remaining = failed;
+
+            return live;
+        })
+        .collect_vec();
+}
+
+/// Follows the boxes of parts through the workflows like evaluation would, noting every
+/// time a non-empty box comes back to a workflow already on its path. Each cycle is
+/// rotated to begin at its earliest workflow so the same loop is found only once.
+fn feasible_cycles(set: &WorkflowSet, workflow: usize, region: Hyperrect, path: &mut Vec<usize>, found: &mut Vec<Vec<usize>>) {
+    let mut remaining = vec![region];
+
+    for r in &set.workflows[workflow].rules {
+        let passed = match &r.condition {
+            None => remaining.drain(..).collect_vec(),
+            Some(c) => {
+                let mut passed = vec![];
+                let mut failed = vec![];
+                for b in remaining {
+                    let (pass, fail) = b.split_by(c);
+                    passed.extend(pass);
+                    failed.extend(fail);
+                }
+                remaining = failed;
+                passed
+            }
+        };
+
+        let Target::Workflow(name) = &r.target else { continue };
+        let Some(next) = set.position(name) else { continue };
+        for p in passed {
+            if let Some(at) = path.iter().position(|w| *w == next) {
+                let mut cycle = path[at..].to_vec();
+                let first = cycle.iter().position_min().unwrap();
+                cycle.rotate_left(first);
+                found.push(cycle);
+                continue;
+            }
+
+            path.push(next);
+            feasible_cycles(set, next, p, path, found);
+            path.pop();
+        }
+    }
+}
+
+/// Checks a workflow set walked from `start` for parts rated inside `domain`. Reachability
+/// and the always-accept/reject checks only follow rules that can fire.
+pub fn analyze(set: &WorkflowSet, start: &str, domain: &Hyperrect) -> Vec<Issue> {
+    let mut issues = vec![];
+    let workflows = &set.workflows;
+
+    let live = workflows.iter().map(|w| live_rules(w, domain)).collect_vec();
+    let edges = workflows
+        .iter()
+        .zip(&live)
+        .map(|(w, l)| {
+            w.rules
+                .iter()
+                .zip(l)
+                .filter(|(_, live)| **live)
+                .filter_map(|(r, _)| match &r.target {
+                    Target::Workflow(name) => set.position(name),
+                    _ => None
+                })
+                .unique()
+                .collect_vec()
+        })
+        .collect_vec();
+
+    let start_idx = set.position(start);
+    if start_idx.is_none() {
+        issues.push(Issue::MissingStart { name: start.to_owned() });
+    }
+
+    for w in workflows {
+        for (i, r) in w.rules.iter().enumerate() {
+            if let Target::Workflow(name) = &r.target {
+                if set.get(name).is_none() {
+                    issues.push(Issue::UndefinedTarget { workflow: w.name.clone(), rule: i, target: name.clone() });
+                }
+            }
+        }
+    }
+
+    let components = strongly_connected_components(&edges);
+    let mut cycles = components
+        .iter()
+        .filter(|c| c.len() > 1 || edges[c[0]].contains(&c[0]))
+        .map(|c| c.iter().sorted().map(|i| workflows[*i].name.clone()).collect_vec())
+        .collect_vec();
+    cycles.sort_by_key(|c| set.position(&c[0]));
+    issues.extend(cycles.into_iter().map(|workflows| Issue::GraphCycle { workflows }));
+
+    if let Some(s) = start_idx {
+        let mut found = vec![];
+        feasible_cycles(set, s, domain.clone(), &mut vec![s], &mut found);
+        issues.extend(
+            found.into_iter()
+                .sorted()
+                .dedup()
+                .map(|c| Issue::Cycle { workflows: c.iter().map(|i| workflows[*i].name.clone()).collect_vec() })
+        );
+    }
+
+    if let Some(s) = start_idx {
+        let mut reached = vec![false; workflows.len()];
+        let mut queue = VecDeque::from([s]);
+        reached[s] = true;
+        while let Some(w) = queue.pop_front() {
+            for n in &edges[w] {
+                if !reached[*n] {
+                    reached[*n] = true;
+                    queue.push_back(*n);
+                }
+            }
+        }
+
+        issues.extend(
+            workflows.iter()
+                .zip(reached)
+                .filter(|(_, r)| !r)
+                .map(|(w, _)| Issue::Unreachable { workflow: w.name.clone() })
+        );
+    }
+
+    for (w, l) in workflows.iter().zip(&live) {
+        issues.extend(
+            l.iter()
+                .positions(|live| !live)
+                .map(|rule| Issue::DeadRule { workflow: w.name.clone(), rule })
+        );
+    }
+
+    // components come sinks first, so a workflow's successors are always settled;
+    // `None` stands for an undefined target, whose outcome is unknown
+    let mut outcomes: Vec<HashSet<Option<Verdict>>> = vec![HashSet::new(); workflows.len()];
+    for component in &components {
+        let mut found = HashSet::new();
+        for m in component {
+            for (r, _) in workflows[*m].rules.iter().zip(&live[*m]).filter(|(_, live)| **live) {
+                match &r.target {
+                    Target::Accept => { found.insert(Some(Verdict::Accept)); }
+                    Target::Reject => { found.insert(Some(Verdict::Reject)); }
+                    Target::Workflow(name) if set.get(name).is_none() => { found.insert(None); }
+                    Target::Workflow(_) => {}
+                }
+            }
+            for n in edges[*m].iter().filter(|n| !component.contains(n)) {
+                found.extend(outcomes[*n].iter().copied());
+            }
+        }
+
+        for m in component {
+            outcomes[*m] = found.clone();
+        }
+    }
+
+    for (w, o) in workflows.iter().zip(&outcomes) {
+        if *o == HashSet::from([Some(Verdict::Accept)]) {
+            issues.push(Issue::AlwaysAccepts { workflow: w.name.clone() });
+        } else if *o == HashSet::from([Some(Verdict::Reject)]) {
+            issues.push(Issue::AlwaysRejects { workflow: w.name.clone() });
+        }
+    }
+
+    return issues;
+}